@@ -13,14 +13,15 @@ use rubato::sinc_interpolator::sinc_interpolator_sse::SseInterpolator;
 #[cfg(feature = "fft_resampler")]
 use rubato::FftFixedIn;
 use rubato::{
-    FastFixedIn, PolynomialDegree, Resampler, SincFixedIn, SincInterpolationType, WindowFunction,
+    FastFixedIn, PolynomialDegree, Resampler, SincFixedIn, SincInterpolationParameters,
+    SincInterpolationType, SincPhase, WindowFunction,
 };
 
 #[cfg(feature = "fft_resampler")]
 fn bench_fftfixedin(c: &mut Criterion) {
     let chunksize = 1024;
     let mut resampler = FftFixedIn::<f64>::new(44100, 192000, 1024, 2, 1).unwrap();
-    let waveform = vec![vec![0.0 as f64; chunksize]; 1];
+    let waveform = vec![vec![0.0f64; chunksize]; 1];
     c.bench_function("FftFixedIn f64", |b| {
         b.iter(|| resampler.process(black_box(&waveform), None).unwrap())
     });
@@ -30,7 +31,7 @@ fn bench_fftfixedin(c: &mut Criterion) {
 fn bench_fftfixedin_32(c: &mut Criterion) {
     let chunksize = 1024;
     let mut resampler = FftFixedIn::<f32>::new(44100, 192000, 1024, 2, 1).unwrap();
-    let waveform = vec![vec![0.0 as f32; chunksize]; 1];
+    let waveform = vec![vec![0.0f32; chunksize]; 1];
     c.bench_function("FftFixedIn f32", |b| {
         b.iter(|| resampler.process(black_box(&waveform), None).unwrap())
     });
@@ -51,7 +52,7 @@ macro_rules! bench_async_resampler {
         fn $f(c: &mut Criterion) {
             let chunksize = 1024;
             let sinc_len = 256;
-            let f_cutoff = 0.9473371669037001;
+            let f_cutoff = 0.947_337_2;
             let oversampling_factor = 256;
             let window = WindowFunction::BlackmanHarris2;
             let resample_ratio = 192000 as f64 / 44100 as f64;
@@ -62,6 +63,7 @@ macro_rules! bench_async_resampler {
                 oversampling_factor,
                 f_cutoff,
                 window,
+                SincPhase::Linear,
             );
             let interpolator = unwrap_helper!($($unwrap)* interpolator);
             let interpolator = Box::new(interpolator);
@@ -353,6 +355,45 @@ bench_fast_async_resampler!(
     "fast async nearest 64"
 );
 
+macro_rules! bench_sinc_preset {
+    ($ft:ty, $preset:ident, $f:ident, $desc:literal) => {
+        fn $f(c: &mut Criterion) {
+            let chunksize = 1024;
+            let resample_ratio = 192000 as f64 / 44100 as f64;
+            let parameters = SincInterpolationParameters::$preset();
+            let mut resampler =
+                SincFixedIn::<$ft>::new(resample_ratio, 1.1, parameters, chunksize, 1).unwrap();
+            let waveform = vec![vec![0.0 as $ft; chunksize]; 1];
+            c.bench_function($desc, |b| {
+                b.iter(|| resampler.process(black_box(&waveform), None).unwrap())
+            });
+        }
+    };
+}
+
+bench_sinc_preset!(f64, draft, bench_preset_draft_64, "preset draft     64");
+bench_sinc_preset!(
+    f64,
+    balanced,
+    bench_preset_balanced_64,
+    "preset balanced  64"
+);
+bench_sinc_preset!(f64, high, bench_preset_high_64, "preset high      64");
+bench_sinc_preset!(
+    f64,
+    mastering,
+    bench_preset_mastering_64,
+    "preset mastering 64"
+);
+
+criterion_group!(
+    preset_benches,
+    bench_preset_draft_64,
+    bench_preset_balanced_64,
+    bench_preset_high_64,
+    bench_preset_mastering_64,
+);
+
 #[cfg(feature = "fft_resampler")]
 criterion_group!(fft_benches, bench_fftfixedin, bench_fftfixedin_32,);
 
@@ -417,6 +458,6 @@ criterion_group!(
 );
 
 #[cfg(feature = "fft_resampler")]
-criterion_main!(benches, fft_benches);
+criterion_main!(benches, preset_benches, fft_benches);
 #[cfg(not(feature = "fft_resampler"))]
-criterion_main!(benches);
+criterion_main!(benches, preset_benches);
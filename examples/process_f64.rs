@@ -140,6 +140,7 @@ fn main() {
                 interpolation,
                 oversampling_factor,
                 window,
+                ..Default::default()
             };
             Box::new(SincFixedIn::<f64>::new(f_ratio, 1.1, params, 1024, channels).unwrap())
         }
@@ -156,6 +157,7 @@ fn main() {
                 interpolation,
                 oversampling_factor,
                 window,
+                ..Default::default()
             };
             Box::new(SincFixedOut::<f64>::new(f_ratio, 1.1, params, 1024, channels).unwrap())
         }
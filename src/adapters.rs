@@ -0,0 +1,758 @@
+use crate::Sample;
+
+/// Read access to a buffer of audio data.
+///
+/// The resamplers read their input through this trait,
+/// which allows them to take data stored in any layout without first copying it.
+/// It is implemented for slices, vectors and arrays of channels,
+/// where each channel can be referenced as a slice ([AsRef<\[T\]>](AsRef)).
+/// For data stored in a single slice, use one of the wrappers
+/// [InterleavedSlice], [SequentialSlice] or [StridedSlice].
+///
+/// Implement this trait to let the resamplers read directly from a custom buffer type.
+/// Only [nbr_channels](AudioInput::nbr_channels), [nbr_frames](AudioInput::nbr_frames)
+/// and [read_sample](AudioInput::read_sample) are required.
+/// The provided [copy_from_channel_to_slice](AudioInput::copy_from_channel_to_slice)
+/// reads one sample at a time, and may be overridden with a faster implementation.
+///
+/// ```
+/// use rubato::AudioInput;
+///
+/// /// A buffer holding a constant value on all channels.
+/// struct Constant {
+///     value: f64,
+///     channels: usize,
+///     frames: usize,
+/// }
+///
+/// impl AudioInput<f64> for Constant {
+///     fn nbr_channels(&self) -> usize {
+///         self.channels
+///     }
+///
+///     fn nbr_frames(&self, _channel: usize) -> usize {
+///         self.frames
+///     }
+///
+///     fn read_sample(&self, _channel: usize, _frame: usize) -> f64 {
+///         self.value
+///     }
+/// }
+/// ```
+pub trait AudioInput<T: Copy> {
+    /// Get the number of channels.
+    fn nbr_channels(&self) -> usize;
+
+    /// Get the number of frames available for the given channel.
+    fn nbr_frames(&self, channel: usize) -> usize;
+
+    /// Read the sample at the given channel and frame.
+    fn read_sample(&self, channel: usize, frame: usize) -> T;
+
+    /// Copy samples from one channel, starting at frame `skip`, into a slice.
+    /// The number of samples copied is given by the length of the slice.
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        for (frame, value) in slice.iter_mut().enumerate() {
+            *value = self.read_sample(channel, skip + frame);
+        }
+    }
+}
+
+/// Write access to a buffer of audio data.
+///
+/// The resamplers write their output through this trait.
+/// It is implemented for the same types as [AudioInput],
+/// with the additional requirement that the channels must be mutable ([AsMut<\[T\]>](AsMut)).
+///
+/// Only [nbr_channels](AudioOutput::nbr_channels), [nbr_frames](AudioOutput::nbr_frames)
+/// and [write_sample](AudioOutput::write_sample) are required.
+/// The provided [copy_from_slice_to_channel](AudioOutput::copy_from_slice_to_channel)
+/// writes one sample at a time, and may be overridden with a faster implementation.
+pub trait AudioOutput<T: Copy> {
+    /// Get the number of channels.
+    fn nbr_channels(&self) -> usize;
+
+    /// Get the number of frames that can be written to the given channel.
+    fn nbr_frames(&self, channel: usize) -> usize;
+
+    /// Write a sample to the given channel and frame.
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T);
+
+    /// Copy samples from a slice into one channel, starting at frame `skip`.
+    /// The number of samples copied is given by the length of the slice.
+    fn copy_from_slice_to_channel(&mut self, channel: usize, skip: usize, slice: &[T]) {
+        for (frame, value) in slice.iter().enumerate() {
+            self.write_sample(channel, skip + frame, *value);
+        }
+    }
+}
+
+impl<T, V> AudioInput<T> for [V]
+where
+    T: Copy,
+    V: AsRef<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.len()
+    }
+
+    fn nbr_frames(&self, channel: usize) -> usize {
+        self[channel].as_ref().len()
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        self[channel].as_ref()[frame]
+    }
+
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        slice.copy_from_slice(&self[channel].as_ref()[skip..skip + slice.len()]);
+    }
+}
+
+impl<T, V> AudioOutput<T> for [V]
+where
+    T: Copy,
+    V: AsRef<[T]> + AsMut<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.len()
+    }
+
+    fn nbr_frames(&self, channel: usize) -> usize {
+        self[channel].as_ref().len()
+    }
+
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+        self[channel].as_mut()[frame] = value;
+    }
+
+    fn copy_from_slice_to_channel(&mut self, channel: usize, skip: usize, slice: &[T]) {
+        self[channel].as_mut()[skip..skip + slice.len()].copy_from_slice(slice);
+    }
+}
+
+impl<T, I> AudioInput<T> for &I
+where
+    T: Copy,
+    I: AudioInput<T> + ?Sized,
+{
+    fn nbr_channels(&self) -> usize {
+        (**self).nbr_channels()
+    }
+
+    fn nbr_frames(&self, channel: usize) -> usize {
+        (**self).nbr_frames(channel)
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        (**self).read_sample(channel, frame)
+    }
+
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        (**self).copy_from_channel_to_slice(channel, skip, slice)
+    }
+}
+
+impl<T, O> AudioOutput<T> for &mut O
+where
+    T: Copy,
+    O: AudioOutput<T> + ?Sized,
+{
+    fn nbr_channels(&self) -> usize {
+        (**self).nbr_channels()
+    }
+
+    fn nbr_frames(&self, channel: usize) -> usize {
+        (**self).nbr_frames(channel)
+    }
+
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+        (**self).write_sample(channel, frame, value)
+    }
+
+    fn copy_from_slice_to_channel(&mut self, channel: usize, skip: usize, slice: &[T]) {
+        (**self).copy_from_slice_to_channel(channel, skip, slice)
+    }
+}
+
+/// Helper macro for implementing the traits for types that dereference to a slice of channels.
+macro_rules! impl_via_slice {
+    ($type:ty, $($generics:tt)*) => {
+        impl<T, V, $($generics)*> AudioInput<T> for $type
+        where
+            T: Copy,
+            V: AsRef<[T]>,
+        {
+            fn nbr_channels(&self) -> usize {
+                self.len()
+            }
+
+            fn nbr_frames(&self, channel: usize) -> usize {
+                self[..].nbr_frames(channel)
+            }
+
+            fn read_sample(&self, channel: usize, frame: usize) -> T {
+                self[..].read_sample(channel, frame)
+            }
+
+            fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+                self[..].copy_from_channel_to_slice(channel, skip, slice)
+            }
+        }
+
+        impl<T, V, $($generics)*> AudioOutput<T> for $type
+        where
+            T: Copy,
+            V: AsRef<[T]> + AsMut<[T]>,
+        {
+            fn nbr_channels(&self) -> usize {
+                self.len()
+            }
+
+            fn nbr_frames(&self, channel: usize) -> usize {
+                AudioOutput::nbr_frames(&self[..], channel)
+            }
+
+            fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+                self[..].write_sample(channel, frame, value)
+            }
+
+            fn copy_from_slice_to_channel(&mut self, channel: usize, skip: usize, slice: &[T]) {
+                self[..].copy_from_slice_to_channel(channel, skip, slice)
+            }
+        }
+    };
+}
+
+impl_via_slice!(Vec<V>,);
+impl_via_slice!([V; N], const N: usize);
+
+/// A wrapper for a slice (or anything that can be referenced as a slice)
+/// containing interleaved samples, `[L0, R0, L1, R1, ...]` for a stereo stream.
+/// The number of frames is given by the length of the slice divided by the number of channels.
+pub struct InterleavedSlice<B> {
+    buf: B,
+    channels: usize,
+}
+
+impl<B> InterleavedSlice<B> {
+    /// Wrap a buffer of interleaved samples with the given number of channels.
+    pub fn new(buf: B, channels: usize) -> Self {
+        Self { buf, channels }
+    }
+
+    /// Get back the wrapped buffer.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+impl<T, B> AudioInput<T> for InterleavedSlice<B>
+where
+    T: Copy,
+    B: AsRef<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        self.buf
+            .as_ref()
+            .len()
+            .checked_div(self.channels)
+            .unwrap_or(0)
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        self.buf.as_ref()[frame * self.channels + channel]
+    }
+
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        let samples = self.buf.as_ref()[(skip * self.channels + channel)..]
+            .iter()
+            .step_by(self.channels);
+        for (value, sample) in slice.iter_mut().zip(samples) {
+            *value = *sample;
+        }
+    }
+}
+
+impl<T, B> AudioOutput<T> for InterleavedSlice<B>
+where
+    T: Copy,
+    B: AsRef<[T]> + AsMut<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        self.buf
+            .as_ref()
+            .len()
+            .checked_div(self.channels)
+            .unwrap_or(0)
+    }
+
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+        self.buf.as_mut()[frame * self.channels + channel] = value;
+    }
+
+    fn copy_from_slice_to_channel(&mut self, channel: usize, skip: usize, slice: &[T]) {
+        let channels = self.channels;
+        let samples = self.buf.as_mut()[(skip * channels + channel)..]
+            .iter_mut()
+            .step_by(channels);
+        for (sample, value) in samples.zip(slice.iter()) {
+            *sample = *value;
+        }
+    }
+}
+
+/// A wrapper for a slice (or anything that can be referenced as a slice)
+/// containing the channels stored one after the other, `[L0, L1, L2, ..., R0, R1, R2, ...]`.
+/// The number of frames is given by the length of the slice divided by the number of channels.
+pub struct SequentialSlice<B> {
+    buf: B,
+    channels: usize,
+}
+
+impl<B> SequentialSlice<B> {
+    /// Wrap a buffer of sequential channels with the given number of channels.
+    pub fn new(buf: B, channels: usize) -> Self {
+        Self { buf, channels }
+    }
+
+    /// Get back the wrapped buffer.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+impl<T, B> AudioInput<T> for SequentialSlice<B>
+where
+    T: Copy,
+    B: AsRef<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        self.buf
+            .as_ref()
+            .len()
+            .checked_div(self.channels)
+            .unwrap_or(0)
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        let frames = AudioInput::nbr_frames(self, channel);
+        self.buf.as_ref()[channel * frames + frame]
+    }
+
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        let start = channel * AudioInput::nbr_frames(self, channel) + skip;
+        slice.copy_from_slice(&self.buf.as_ref()[start..start + slice.len()]);
+    }
+}
+
+impl<T, B> AudioOutput<T> for SequentialSlice<B>
+where
+    T: Copy,
+    B: AsRef<[T]> + AsMut<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        self.buf
+            .as_ref()
+            .len()
+            .checked_div(self.channels)
+            .unwrap_or(0)
+    }
+
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+        let frames = AudioOutput::nbr_frames(self, channel);
+        self.buf.as_mut()[channel * frames + frame] = value;
+    }
+
+    fn copy_from_slice_to_channel(&mut self, channel: usize, skip: usize, slice: &[T]) {
+        let start = channel * AudioOutput::nbr_frames(self, channel) + skip;
+        self.buf.as_mut()[start..start + slice.len()].copy_from_slice(slice);
+    }
+}
+
+/// A wrapper for a slice (or anything that can be referenced as a slice)
+/// where the samples are placed at a constant distance, or stride, from each other.
+/// The sample for a given channel and frame is found at index
+/// `channel * channel_stride + frame * frame_stride`.
+///
+/// This can describe both interleaved and sequential data,
+/// as well as for example interleaved data with extra unused channels,
+/// or a column-major matrix with padding between the columns.
+pub struct StridedSlice<B> {
+    buf: B,
+    channels: usize,
+    frames: usize,
+    channel_stride: usize,
+    frame_stride: usize,
+}
+
+impl<B> StridedSlice<B> {
+    /// Wrap a buffer of strided samples.
+    ///
+    /// Parameters are:
+    /// - `buf`: The buffer holding the samples.
+    /// - `channels`: Number of channels.
+    /// - `frames`: Number of frames.
+    /// - `channel_stride`: Distance between the first samples of two consecutive channels.
+    /// - `frame_stride`: Distance between two consecutive samples of the same channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is too short to hold the given number of channels and frames.
+    pub fn new<T>(
+        buf: B,
+        channels: usize,
+        frames: usize,
+        channel_stride: usize,
+        frame_stride: usize,
+    ) -> Self
+    where
+        B: AsRef<[T]>,
+    {
+        if channels > 0 && frames > 0 {
+            let last_index = (channels - 1) * channel_stride + (frames - 1) * frame_stride;
+            assert!(
+                last_index < buf.as_ref().len(),
+                "Buffer of length {} is too short for {} channels and {} frames, must be at least {}",
+                buf.as_ref().len(),
+                channels,
+                frames,
+                last_index + 1
+            );
+        }
+        Self {
+            buf,
+            channels,
+            frames,
+            channel_stride,
+            frame_stride,
+        }
+    }
+
+    /// Get back the wrapped buffer.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+
+    fn index(&self, channel: usize, frame: usize) -> usize {
+        channel * self.channel_stride + frame * self.frame_stride
+    }
+}
+
+impl<T, B> AudioInput<T> for StridedSlice<B>
+where
+    T: Copy,
+    B: AsRef<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        self.frames
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        self.buf.as_ref()[self.index(channel, frame)]
+    }
+}
+
+impl<T, B> AudioOutput<T> for StridedSlice<B>
+where
+    T: Copy,
+    B: AsRef<[T]> + AsMut<[T]>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        self.frames
+    }
+
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+        let index = self.index(channel, frame);
+        self.buf.as_mut()[index] = value;
+    }
+}
+
+/// A wrapper that pads an input with zeros,
+/// so that any frame beyond the end of the wrapped input reads as zero.
+/// The wrapper always has the given number of channels,
+/// channels missing in the wrapped input read as zeros,
+/// and any extra channels of the input are ignored.
+/// A wrapper without an input reads as zeros for all frames.
+pub(crate) struct ZeroPadded<'a, I: ?Sized> {
+    input: Option<&'a I>,
+    channels: usize,
+}
+
+impl<'a, I: ?Sized> ZeroPadded<'a, I> {
+    /// Wrap an optional input with the given number of channels.
+    pub fn new(input: Option<&'a I>, channels: usize) -> Self {
+        Self { input, channels }
+    }
+}
+
+impl<'a, T, I> AudioInput<T> for ZeroPadded<'a, I>
+where
+    T: Sample,
+    I: AudioInput<T> + ?Sized,
+{
+    fn nbr_channels(&self) -> usize {
+        self.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        usize::MAX
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        match self.input {
+            Some(input) if channel < input.nbr_channels() && frame < input.nbr_frames(channel) => {
+                input.read_sample(channel, frame)
+            }
+            _ => T::zero(),
+        }
+    }
+
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        let available = self
+            .input
+            .filter(|input| channel < input.nbr_channels())
+            .map(|input| input.nbr_frames(channel).saturating_sub(skip))
+            .unwrap_or(0)
+            .min(slice.len());
+        if let Some(input) = self.input.filter(|_| available > 0) {
+            input.copy_from_channel_to_slice(channel, skip, &mut slice[..available]);
+        }
+        slice[available..].iter_mut().for_each(|v| *v = T::zero());
+    }
+}
+
+/// A wrapper that reads a window of frames of an input.
+/// Frame 0 of the wrapper is frame `offset` of the wrapped input,
+/// and the window ends at frame `end` of the wrapped input, or at the end of the channel if that comes first.
+pub(crate) struct InputWindow<'a, I: ?Sized> {
+    input: &'a I,
+    offset: usize,
+    end: usize,
+}
+
+impl<'a, I: ?Sized> InputWindow<'a, I> {
+    /// Wrap an input, to read its frames from `offset` up to, but not including, `end`.
+    pub fn new(input: &'a I, offset: usize, end: usize) -> Self {
+        Self { input, offset, end }
+    }
+}
+
+impl<'a, T, I> AudioInput<T> for InputWindow<'a, I>
+where
+    T: Sample,
+    I: AudioInput<T> + ?Sized,
+{
+    fn nbr_channels(&self) -> usize {
+        self.input.nbr_channels()
+    }
+
+    fn nbr_frames(&self, channel: usize) -> usize {
+        self.input
+            .nbr_frames(channel)
+            .min(self.end)
+            .saturating_sub(self.offset)
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        self.input.read_sample(channel, self.offset + frame)
+    }
+
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        self.input
+            .copy_from_channel_to_slice(channel, self.offset + skip, slice);
+    }
+}
+
+/// A wrapper that writes to a window of frames of an output.
+/// Frame 0 of the wrapper is frame `offset` of the wrapped output,
+/// and frames at or beyond `end` of the wrapped output are discarded.
+/// The wrapper accepts any number of frames.
+pub(crate) struct OutputWindow<'a, O: ?Sized> {
+    output: &'a mut O,
+    offset: usize,
+    end: usize,
+}
+
+impl<'a, O: ?Sized> OutputWindow<'a, O> {
+    /// Wrap an output, to write to its frames from `offset` up to, but not including, `end`.
+    pub fn new(output: &'a mut O, offset: usize, end: usize) -> Self {
+        Self {
+            output,
+            offset,
+            end,
+        }
+    }
+}
+
+impl<'a, T, O> AudioOutput<T> for OutputWindow<'a, O>
+where
+    T: Sample,
+    O: AudioOutput<T> + ?Sized,
+{
+    fn nbr_channels(&self) -> usize {
+        self.output.nbr_channels()
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        usize::MAX
+    }
+
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+        if self.offset + frame < self.end {
+            self.output
+                .write_sample(channel, self.offset + frame, value);
+        }
+    }
+
+    fn copy_from_slice_to_channel(&mut self, channel: usize, skip: usize, slice: &[T]) {
+        let start = self.offset + skip;
+        let available = self.end.saturating_sub(start).min(slice.len());
+        if available > 0 {
+            self.output
+                .copy_from_slice_to_channel(channel, start, &slice[..available]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AudioInput, AudioOutput, InterleavedSlice, SequentialSlice, StridedSlice};
+    use super::{InputWindow, OutputWindow, ZeroPadded};
+    use test_log::test;
+
+    #[test]
+    fn read_interleaved() {
+        let data = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let input = InterleavedSlice::new(&data[..], 2);
+        assert_eq!(input.nbr_channels(), 2);
+        assert_eq!(input.nbr_frames(0), 3);
+        assert_eq!(input.read_sample(0, 1), 3.0);
+        assert_eq!(input.read_sample(1, 2), 6.0);
+        let mut values = [0.0; 2];
+        input.copy_from_channel_to_slice(1, 1, &mut values);
+        assert_eq!(values, [4.0, 6.0]);
+    }
+
+    #[test]
+    fn write_interleaved() {
+        let mut data = [0.0; 6];
+        let mut output = InterleavedSlice::new(&mut data[..], 3);
+        assert_eq!(AudioOutput::nbr_frames(&output, 0), 2);
+        output.write_sample(2, 0, 1.0);
+        output.copy_from_slice_to_channel(1, 0, &[2.0, 3.0]);
+        assert_eq!(data, [0.0, 2.0, 1.0, 0.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn read_write_sequential() {
+        let mut data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let input = SequentialSlice::new(&data, 2);
+        assert_eq!(input.nbr_frames(0), 3);
+        assert_eq!(input.read_sample(1, 0), 4.0);
+        let mut values = [0.0; 2];
+        input.copy_from_channel_to_slice(1, 1, &mut values);
+        assert_eq!(values, [5.0, 6.0]);
+        let mut output = SequentialSlice::new(&mut data, 3);
+        output.write_sample(2, 1, 0.0);
+        output.copy_from_slice_to_channel(0, 0, &[7.0, 8.0]);
+        assert_eq!(data, [7.0, 8.0, 3.0, 4.0, 5.0, 0.0]);
+    }
+
+    #[test]
+    fn read_write_strided() {
+        // Two channels, interleaved with an unused third channel.
+        let mut data = [1.0, 2.0, 0.0, 3.0, 4.0, 0.0];
+        let input = StridedSlice::new(&data[..], 2, 2, 1, 3);
+        assert_eq!(input.nbr_frames(0), 2);
+        assert_eq!(input.read_sample(1, 1), 4.0);
+        let mut values = [0.0; 2];
+        input.copy_from_channel_to_slice(0, 0, &mut values);
+        assert_eq!(values, [1.0, 3.0]);
+        let mut output = StridedSlice::new(&mut data[..], 2, 2, 1, 3);
+        output.copy_from_slice_to_channel(1, 0, &[5.0, 6.0]);
+        assert_eq!(data, [1.0, 5.0, 0.0, 3.0, 6.0, 0.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn strided_too_short() {
+        let data = [0.0; 4];
+        let _ = StridedSlice::new(&data[..], 2, 2, 1, 3);
+    }
+
+    #[test]
+    fn read_write_vec_of_vecs() {
+        let mut data = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        assert_eq!(AudioInput::nbr_channels(&data), 2);
+        assert_eq!(data.read_sample(1, 0), 3.0);
+        data.write_sample(0, 1, 5.0);
+        assert_eq!(data, [[1.0, 5.0], [3.0, 4.0]]);
+    }
+
+    #[test]
+    fn read_zero_padded() {
+        let data = [vec![1.0, 2.0], vec![3.0]];
+        let padded = ZeroPadded::new(Some(&data), 2);
+        assert_eq!(padded.read_sample(0, 1), 2.0);
+        assert_eq!(padded.read_sample(1, 1), 0.0);
+        let mut values = [9.0; 4];
+        padded.copy_from_channel_to_slice(0, 1, &mut values);
+        assert_eq!(values, [2.0, 0.0, 0.0, 0.0]);
+        let padded = ZeroPadded::new(Some(&data), 3);
+        assert_eq!(padded.nbr_channels(), 3);
+        assert_eq!(padded.read_sample(2, 0), 0.0);
+        padded.copy_from_channel_to_slice(2, 0, &mut values);
+        assert_eq!(values, [0.0; 4]);
+        let empty = ZeroPadded::<[Vec<f64>]>::new(None, 2);
+        assert_eq!(empty.nbr_channels(), 2);
+        empty.copy_from_channel_to_slice(0, 0, &mut values);
+        assert_eq!(values, [0.0; 4]);
+    }
+
+    #[test]
+    fn read_input_window() {
+        let data = vec![vec![1.0, 2.0, 3.0, 4.0], vec![5.0, 6.0, 7.0]];
+        let window = InputWindow::new(&data, 1, 4);
+        assert_eq!(window.nbr_frames(0), 3);
+        assert_eq!(window.nbr_frames(1), 2);
+        assert_eq!(window.read_sample(1, 0), 6.0);
+        let mut values = [0.0; 2];
+        window.copy_from_channel_to_slice(0, 1, &mut values);
+        assert_eq!(values, [3.0, 4.0]);
+    }
+
+    #[test]
+    fn write_output_window() {
+        let mut data = vec![vec![0.0; 5]; 2];
+        let mut window = OutputWindow::new(&mut data, 1, 3);
+        window.write_sample(0, 0, 1.0);
+        window.write_sample(0, 2, 2.0);
+        window.copy_from_slice_to_channel(1, 0, &[3.0, 4.0, 5.0]);
+        assert_eq!(data, [[0.0, 1.0, 0.0, 0.0, 0.0], [0.0, 3.0, 4.0, 0.0, 0.0]]);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::asynchro_sinc::{interp_cubic, interp_lin, interp_quad};
+use crate::sinc::make_sincs;
+use crate::{SincInterpolationParameters, SincInterpolationType};
+use std::f64::consts::PI;
+
+/// One point of a frequency response.
+#[derive(Debug, Clone, Copy)]
+pub struct ResponsePoint {
+    /// Frequency, relative to the lowest one of fs_in/2 or fs_out/2.
+    pub frequency: f64,
+    /// Magnitude in dB, relative to the gain at zero frequency.
+    pub magnitude: f64,
+    /// Phase in radians, relative to the center of the sinc filter.
+    /// This is zero for frequencies in the passband of a linear phase filter.
+    pub phase: f64,
+}
+
+/// A summary of the properties of the effective filter of a sinc resampler.
+///
+/// All frequencies are relative to the lowest one of fs_in/2 or fs_out/2.
+///
+/// The transition band of a windowed sinc filter is symmetric around the -6 dB point.
+/// The stopband edge is found as the lowest frequency above which the attenuation
+/// stays above the stopband attenuation,
+/// and the passband edge is placed symmetrically on the other side of the -6 dB point.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterAnalysis {
+    /// The end of the passband.
+    pub passband_edge: f64,
+    /// The peak-to-peak variation of the magnitude in the passband, in dB.
+    pub passband_ripple: f64,
+    /// The lowest frequency at which the magnitude has fallen by 3 dB.
+    pub cutoff_3db: f64,
+    /// The lowest frequency at which the magnitude has fallen by 6 dB.
+    pub cutoff_6db: f64,
+    /// The start of the stopband.
+    pub stopband_edge: f64,
+    /// The smallest attenuation, in dB, at any frequency above
+    /// the lowest one of fs_in/2 or fs_out/2.
+    /// This includes the images caused by the interpolation between the points
+    /// of the oversampled sinc table.
+    pub stopband_attenuation: f64,
+}
+
+/// The effective filter of a sinc resampler, for evaluating its frequency response.
+///
+/// The resampler convolves the input with a continuous impulse response,
+/// obtained by interpolating between the points of the oversampled sinc table.
+/// The frequency response of this impulse response is the response of the sinc table,
+/// multiplied by the response of the interpolation.
+struct EffectiveFilter {
+    prototype: Vec<f64>,
+    oversampling_factor: usize,
+    interpolation: SincInterpolationType,
+    /// Frequency of the lowest Nyquist frequency, in cycles per input sample.
+    nyquist: f64,
+    dc_gain: f64,
+}
+
+impl EffectiveFilter {
+    fn new(parameters: &SincInterpolationParameters, resample_ratio: f64) -> Self {
+        let sinc_len = 8 * (((parameters.sinc_len as f32) / 8.0).ceil() as usize);
+        let f_cutoff = if resample_ratio >= 1.0 {
+            parameters.f_cutoff
+        } else {
+            parameters.f_cutoff * resample_ratio as f32
+        };
+        let factor = parameters.oversampling_factor;
+        let sincs = make_sincs::<f64>(
+            sinc_len,
+            factor,
+            f_cutoff,
+            parameters.window,
+            parameters.phase,
+        );
+        let mut prototype = vec![0.0; sinc_len * factor];
+        for p in 0..sinc_len {
+            for n in 0..factor {
+                prototype[factor * p + n] = sincs[factor - n - 1][p];
+            }
+        }
+        let mut filter = Self {
+            prototype,
+            oversampling_factor: factor,
+            interpolation: parameters.interpolation,
+            nyquist: 0.5 * resample_ratio.min(1.0),
+            dc_gain: 1.0,
+        };
+        filter.dc_gain = filter.response_at(0.0).0;
+        filter
+    }
+
+    /// Response of the sinc table at the given frequency in cycles per input sample,
+    /// with the phase relative to the center of the table.
+    fn table_response(&self, frequency: f64) -> (f64, f64) {
+        let step = -2.0 * PI * frequency / self.oversampling_factor as f64;
+        let (step_im, step_re) = step.sin_cos();
+        let (mut rot_re, mut rot_im) = (1.0, 0.0);
+        let (mut re, mut im) = (0.0, 0.0);
+        for (n, value) in self.prototype.iter().enumerate() {
+            re += value * rot_re;
+            im += value * rot_im;
+            let new_re = rot_re * step_re - rot_im * step_im;
+            rot_im = rot_re * step_im + rot_im * step_re;
+            rot_re = new_re;
+            // Renormalize now and then to avoid accumulating rounding errors.
+            if n % 1024 == 1023 {
+                let norm = (rot_re * rot_re + rot_im * rot_im).sqrt();
+                rot_re /= norm;
+                rot_im /= norm;
+            }
+        }
+        let center = -step * (self.prototype.len() / 2) as f64;
+        let (center_im, center_re) = center.sin_cos();
+        (
+            re * center_re - im * center_im,
+            re * center_im + im * center_re,
+        )
+    }
+
+    /// Response of the interpolation between the table points,
+    /// at the given frequency in cycles per table point.
+    /// The interpolation is a convolution with a piecewise polynomial kernel,
+    /// whose Fourier transform is calculated by numerical integration.
+    fn interpolation_response(&self, frequency: f64) -> (f64, f64) {
+        const STEPS: usize = 64;
+        let (mut re, mut im) = (0.0, 0.0);
+        for step in 0..STEPS {
+            let x = (step as f64 + 0.5) / STEPS as f64;
+            // Add the weight of the table point at `offset` for the position `x`,
+            // and the distance from the point to the position.
+            let mut add = |offset: f64, weight: f64| {
+                let phase = -2.0 * PI * frequency * (x - offset);
+                re += weight * phase.cos();
+                im += weight * phase.sin();
+            };
+            match self.interpolation {
+                SincInterpolationType::Cubic => {
+                    for (d, unit) in [
+                        [1.0, 0.0, 0.0, 0.0],
+                        [0.0, 1.0, 0.0, 0.0],
+                        [0.0, 0.0, 1.0, 0.0],
+                        [0.0, 0.0, 0.0, 1.0],
+                    ]
+                    .iter()
+                    .enumerate()
+                    {
+                        add(d as f64 - 1.0, interp_cubic(x, unit));
+                    }
+                }
+                SincInterpolationType::Quadratic => {
+                    for (d, unit) in [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]
+                        .iter()
+                        .enumerate()
+                    {
+                        add(d as f64, interp_quad(x, unit));
+                    }
+                }
+                SincInterpolationType::Linear => {
+                    for (d, unit) in [[1.0, 0.0], [0.0, 1.0]].iter().enumerate() {
+                        add(d as f64, interp_lin(x, unit));
+                    }
+                }
+                SincInterpolationType::Nearest => {
+                    add(x.round(), 1.0);
+                }
+            }
+        }
+        (re / STEPS as f64, im / STEPS as f64)
+    }
+
+    /// Complex response at the given frequency in cycles per input sample.
+    fn response_at(&self, frequency: f64) -> (f64, f64) {
+        let (t_re, t_im) = self.table_response(frequency);
+        let (i_re, i_im) = self.interpolation_response(frequency / self.oversampling_factor as f64);
+        (
+            (t_re * i_re - t_im * i_im) / self.dc_gain,
+            (t_re * i_im + t_im * i_re) / self.dc_gain,
+        )
+    }
+
+    /// Response at the given relative frequency.
+    fn point(&self, frequency: f64) -> ResponsePoint {
+        let (re, im) = self.response_at(frequency * self.nyquist);
+        ResponsePoint {
+            frequency,
+            magnitude: 10.0 * (re * re + im * im).log10(),
+            phase: im.atan2(re),
+        }
+    }
+
+    fn magnitude(&self, frequency: f64) -> f64 {
+        self.point(frequency).magnitude
+    }
+}
+
+/// Calculate the frequency response of the effective filter of a sinc resampler
+/// with the given parameters and resampling ratio.
+///
+/// The frequencies are given relative to the lowest one of fs_in/2 or fs_out/2,
+/// meaning that 1.0 corresponds to the Nyquist frequency of the lower sample rate.
+/// The response includes the interpolation between the points of the oversampled sinc table,
+/// and is therefore valid also at frequencies far above the Nyquist frequency.
+pub fn sinc_frequency_response(
+    parameters: &SincInterpolationParameters,
+    resample_ratio: f64,
+    frequencies: &[f64],
+) -> Vec<ResponsePoint> {
+    let filter = EffectiveFilter::new(parameters, resample_ratio);
+    frequencies.iter().map(|f| filter.point(*f)).collect()
+}
+
+/// Analyze the effective filter of a sinc resampler with the given parameters and resampling ratio.
+/// See [FilterAnalysis] for the definitions of the properties.
+///
+/// The analysis evaluates the frequency response at a large number of points,
+/// and may take some time for long sincs with high oversampling factors.
+pub fn analyze_sinc_filter(
+    parameters: &SincInterpolationParameters,
+    resample_ratio: f64,
+) -> FilterAnalysis {
+    let filter = EffectiveFilter::new(parameters, resample_ratio);
+    let sinc_len = filter.prototype.len() / filter.oversampling_factor;
+    // Use a step size small enough to resolve the ripples and sidelobes.
+    let step = 0.25 / sinc_len as f64;
+    let magnitudes: Vec<(f64, f64)> = (0..=8 * sinc_len)
+        .map(|n| {
+            let freq = n as f64 * step;
+            (freq, filter.magnitude(freq))
+        })
+        .collect();
+
+    // The sidelobes of the sinc decrease away from the transition band.
+    // Check the first part of the stopband, and the images of the passband
+    // at the first two multiples of the sample rate of the sinc table.
+    let table_rate = filter.oversampling_factor as f64 / filter.nyquist;
+    let images = (1..=2)
+        .flat_map(|image| (-100..=100).map(move |n| image as f64 * table_rate + n as f64 * 0.01));
+    let max_stopband = magnitudes
+        .iter()
+        .filter(|(freq, _)| *freq >= 1.0)
+        .map(|(_, mag)| *mag)
+        .chain(images.map(|freq| filter.magnitude(freq)))
+        .fold(f64::MIN, f64::max);
+    let stopband_attenuation = -max_stopband;
+
+    let cutoff_3db = find_crossing(&filter, &magnitudes, -3.0);
+    let cutoff_6db = find_crossing(&filter, &magnitudes, -6.0);
+    let stopband_edge = magnitudes
+        .iter()
+        .find(|(freq, mag)| *freq > cutoff_6db && *mag <= max_stopband)
+        .map(|(freq, _)| *freq)
+        .unwrap_or(1.0)
+        .min(1.0);
+    let passband_edge = (2.0 * cutoff_6db - stopband_edge).max(0.0);
+    let (min_gain, max_gain) = magnitudes
+        .iter()
+        .take_while(|(freq, _)| *freq <= passband_edge)
+        .fold((f64::MAX, f64::MIN), |(min, max), (_, mag)| {
+            (min.min(*mag), max.max(*mag))
+        });
+
+    FilterAnalysis {
+        passband_edge,
+        passband_ripple: max_gain - min_gain,
+        cutoff_3db,
+        cutoff_6db,
+        stopband_edge,
+        stopband_attenuation,
+    }
+}
+
+/// Find the lowest frequency where the magnitude falls below the given level.
+/// The crossing is first located on the grid of precalculated magnitudes,
+/// and then refined by bisection.
+fn find_crossing(filter: &EffectiveFilter, magnitudes: &[(f64, f64)], level: f64) -> f64 {
+    let index = magnitudes
+        .iter()
+        .position(|(_, mag)| *mag < level)
+        .unwrap_or(magnitudes.len() - 1);
+    if index == 0 {
+        return 0.0;
+    }
+    let mut lower = magnitudes[index - 1].0;
+    let mut upper = magnitudes[index].0;
+    for _ in 0..20 {
+        let middle = 0.5 * (lower + upper);
+        if filter.magnitude(middle) < level {
+            upper = middle;
+        } else {
+            lower = middle;
+        }
+    }
+    0.5 * (lower + upper)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze_sinc_filter, sinc_frequency_response};
+    use crate::{
+        calculate_cutoff, Resampler, SincFixedIn, SincInterpolationParameters,
+        SincInterpolationType, SincPhase, WindowFunction,
+    };
+    use test_log::test;
+
+    fn params() -> SincInterpolationParameters {
+        SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: calculate_cutoff(64, WindowFunction::BlackmanHarris2),
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 64,
+            window: WindowFunction::BlackmanHarris2,
+            phase: SincPhase::Linear,
+        }
+    }
+
+    #[test]
+    fn analyze_filter() {
+        let analysis = analyze_sinc_filter(&params(), 1.5);
+        assert!(analysis.passband_ripple < 0.01, "{:?}", analysis);
+        assert!(analysis.passband_edge > 0.55, "{:?}", analysis);
+        assert!(analysis.cutoff_3db > 0.7 && analysis.cutoff_3db < 0.85);
+        assert!(analysis.cutoff_6db > analysis.cutoff_3db);
+        assert!(analysis.stopband_edge > analysis.cutoff_6db && analysis.stopband_edge <= 1.0);
+        assert!(analysis.stopband_attenuation > 80.0, "{:?}", analysis);
+    }
+
+    #[test]
+    fn better_interpolation_gives_better_stopband() {
+        let linear = analyze_sinc_filter(&params(), 1.5);
+        let mut cubic_params = params();
+        cubic_params.interpolation = SincInterpolationType::Cubic;
+        let cubic = analyze_sinc_filter(&cubic_params, 1.5);
+        let mut nearest_params = params();
+        nearest_params.interpolation = SincInterpolationType::Nearest;
+        let nearest = analyze_sinc_filter(&nearest_params, 1.5);
+        assert!(cubic.stopband_attenuation > linear.stopband_attenuation + 10.0);
+        assert!(linear.stopband_attenuation > nearest.stopband_attenuation + 10.0);
+    }
+
+    #[test]
+    fn cutoff_follows_ratio() {
+        // The -6 dB point of a windowed sinc is at the cutoff frequency,
+        // which is relative to the lowest Nyquist frequency.
+        let f_cutoff = params().f_cutoff as f64;
+        for ratio in [0.5, 2.0] {
+            let analysis = analyze_sinc_filter(&params(), ratio);
+            assert!(
+                (analysis.cutoff_6db - f_cutoff).abs() < 0.01,
+                "{}: {:?}",
+                ratio,
+                analysis
+            );
+        }
+        let response = sinc_frequency_response(&params(), 2.0, &[0.0, 0.5]);
+        assert!(response[0].magnitude.abs() < 1e-9);
+        // The filter is linear phase, which gives zero phase relative to the center.
+        assert!(response[1].phase.abs() < 1e-6);
+    }
+
+    #[test]
+    fn response_matches_resampler() {
+        let ratio = 1.5;
+        let relative_freq = 0.77;
+        let expected = sinc_frequency_response(&params(), ratio, &[relative_freq])[0].magnitude;
+        let mut resampler = SincFixedIn::<f64>::new(ratio, 1.0, params(), 4096, 1).unwrap();
+        let omega = std::f64::consts::PI * relative_freq;
+        let waves_in = vec![(0..4096)
+            .map(|n| (omega * n as f64).sin())
+            .collect::<Vec<f64>>()];
+        let waves_out = resampler.process(&waves_in, None).unwrap();
+        // Skip the start of the output, where the filter is not yet filled.
+        let steady = &waves_out[0][512..waves_out[0].len() - 512];
+        let power = steady.iter().map(|v| v * v).sum::<f64>() / steady.len() as f64;
+        let measured = 10.0 * (2.0 * power).log10();
+        assert!(
+            (measured - expected).abs() < 0.1,
+            "measured {} expected {}",
+            measured,
+            expected
+        );
+    }
+}
@@ -1,5 +1,13 @@
+use crate::adapters::{AudioInput, AudioOutput, ZeroPadded};
 use crate::error::{ResampleError, ResampleResult, ResamplerConstructionError};
-use crate::{update_mask_from_buffers, validate_buffers, Resampler, Sample};
+use crate::state::{
+    first_index_step, total_index_step, validate_state_buffer, validate_state_frames,
+    validate_state_index, validate_state_ratio, AsyncResamplerState,
+};
+use crate::{
+    flush_resampler, partial_input_frames, update_mask_from_buffers, validate_buffers,
+    FrameCounter, Resampler, Sample,
+};
 
 const POLYNOMIAL_LEN_U: usize = 8;
 const POLYNOMIAL_LEN_I: isize = 8;
@@ -13,7 +21,7 @@ macro_rules! t {
 
 /// Degree of the polynomial used for interpolation.
 /// A higher degree gives a higher quality result, while taking longer to compute.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum PolynomialDegree {
     /// Septic polynomial, fitted using 8 sample points.
     Septic,
@@ -40,6 +48,7 @@ pub enum PolynomialDegree {
 ///
 /// The resampling ratio can be freely adjusted within the range specified to the constructor.
 /// Higher maximum ratios require more memory to be allocated by [Resampler::output_buffer_allocate].
+#[derive(Clone)]
 pub struct FastFixedIn<T> {
     nbr_channels: usize,
     chunk_size: usize,
@@ -51,6 +60,7 @@ pub struct FastFixedIn<T> {
     buffer: Vec<Vec<T>>,
     interpolation: PolynomialDegree,
     channel_mask: Vec<bool>,
+    frame_counter: FrameCounter,
 }
 
 /// An asynchronous resampler that returns a fixed number of audio frames.
@@ -68,6 +78,7 @@ pub struct FastFixedIn<T> {
 /// The resampling ratio can be freely adjusted within the range specified to the constructor.
 /// Higher maximum ratios require more memory to be allocated by
 /// [input_buffer_allocate](Resampler::input_buffer_allocate) and an internal buffer.
+#[derive(Clone)]
 pub struct FastFixedOut<T> {
     nbr_channels: usize,
     chunk_size: usize,
@@ -81,6 +92,7 @@ pub struct FastFixedOut<T> {
     buffer: Vec<Vec<T>>,
     interpolation: PolynomialDegree,
     channel_mask: Vec<bool>,
+    frame_counter: FrameCounter,
 }
 
 /// Perform septic polynomial interpolation to get value at x.
@@ -237,20 +249,84 @@ where
             buffer,
             interpolation: interpolation_type,
             channel_mask,
+            frame_counter: FrameCounter::default(),
         })
     }
+
+    /// Take a snapshot of the internal state of the resampler,
+    /// see [AsyncResamplerState] for details.
+    pub fn state(&self) -> AsyncResamplerState<T> {
+        AsyncResamplerState {
+            chunk_size: self.chunk_size,
+            last_index: self.last_index,
+            resample_ratio: self.resample_ratio,
+            target_ratio: self.target_ratio,
+            needed_input_size: 0,
+            buffer_fill: 0,
+            buffer: self.buffer.clone(),
+            output_frames_expected: self.frame_counter.expected,
+            output_frames_produced: self.frame_counter.produced,
+        }
+    }
+
+    /// Restore the internal state from a snapshot taken with [state](FastFixedIn::state).
+    /// The resampler must have been created with the same parameters
+    /// as the one the snapshot was taken from.
+    /// If the snapshot does not match, an error is returned and the state is left unchanged.
+    pub fn set_state(&mut self, state: &AsyncResamplerState<T>) -> ResampleResult<()> {
+        validate_state_buffer(&self.buffer, &state.buffer)?;
+        if state.chunk_size != self.chunk_size {
+            return Err(ResampleError::IncompatibleState);
+        }
+        validate_state_ratio(
+            state.resample_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+        )?;
+        validate_state_ratio(
+            state.target_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+        )?;
+        // The first index of the next chunk must not read before the start of the buffer.
+        let steps = state.chunk_size as f64 * 0.5 * (state.resample_ratio + state.target_ratio);
+        validate_state_index(
+            state.last_index,
+            3.0 - 2.0 * POLYNOMIAL_LEN_U as f64
+                - first_index_step(state.resample_ratio, state.target_ratio, steps),
+            state.chunk_size as f64,
+        )?;
+        validate_state_frames(state.output_frames_expected)?;
+        self.chunk_size = state.chunk_size;
+        self.last_index = state.last_index;
+        self.resample_ratio = state.resample_ratio;
+        self.target_ratio = state.target_ratio;
+        for (buf, saved) in self.buffer.iter_mut().zip(state.buffer.iter()) {
+            buf.copy_from_slice(saved);
+        }
+        self.frame_counter = FrameCounter {
+            expected: state.output_frames_expected,
+            produced: state.output_frames_produced,
+            ..Default::default()
+        };
+        Ok(())
+    }
 }
 
 impl<T> Resampler<T> for FastFixedIn<T>
 where
     T: Sample,
 {
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    fn process_into_buffer<Vin, Vout>(
         &mut self,
-        wave_in: &[Vin],
-        wave_out: &mut [Vout],
+        wave_in: &Vin,
+        wave_out: &mut Vout,
         active_channels_mask: Option<&[bool]>,
-    ) -> ResampleResult<(usize, usize)> {
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -278,8 +354,12 @@ where
 
         for (chan, active) in self.channel_mask.iter().enumerate() {
             if *active {
-                self.buffer[chan][2 * POLYNOMIAL_LEN_U..2 * POLYNOMIAL_LEN_U + self.chunk_size]
-                    .copy_from_slice(&wave_in[chan].as_ref()[..self.chunk_size]);
+                wave_in.copy_from_channel_to_slice(
+                    chan,
+                    0,
+                    &mut self.buffer[chan]
+                        [2 * POLYNOMIAL_LEN_U..2 * POLYNOMIAL_LEN_U + self.chunk_size],
+                );
             }
         }
 
@@ -319,10 +399,7 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 8) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(n) = interp_septic(frac_offset, buf);
+                                wave_out.write_sample(chan, n, interp_septic(frac_offset, buf));
                             }
                         }
                     }
@@ -344,10 +421,7 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 6) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(n) = interp_quintic(frac_offset, buf);
+                                wave_out.write_sample(chan, n, interp_quintic(frac_offset, buf));
                             }
                         }
                     }
@@ -369,10 +443,7 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 4) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(n) = interp_cubic(frac_offset, buf);
+                                wave_out.write_sample(chan, n, interp_cubic(frac_offset, buf));
                             }
                         }
                     }
@@ -394,10 +465,7 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 2) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(n) = interp_lin(frac_offset, buf);
+                                wave_out.write_sample(chan, n, interp_lin(frac_offset, buf));
                             }
                         }
                     }
@@ -416,10 +484,7 @@ where
                                     .buffer
                                     .get_unchecked(chan)
                                     .get_unchecked((start_idx + 2 * POLYNOMIAL_LEN_I) as usize);
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(n) = *point;
+                                wave_out.write_sample(chan, n, *point);
                             }
                         }
                     }
@@ -429,6 +494,11 @@ where
         }
 
         // Store last index for next iteration.
+        self.frame_counter.add(
+            self.chunk_size,
+            n,
+            0.5 * (self.resample_ratio + self.target_ratio),
+        );
         self.last_index = idx - self.chunk_size as f64;
         self.resample_ratio = self.target_ratio;
         trace!(
@@ -455,6 +525,51 @@ where
         (POLYNOMIAL_LEN_U as f64 * self.resample_ratio / 2.0) as usize
     }
 
+    fn process_partial_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let frames = partial_input_frames(wave_in, self.input_frames_next());
+        // The same average ratio as used when counting the chunk.
+        let ratio = 0.5 * (self.resample_ratio + self.target_ratio);
+        let (frames_in, frames_out) = self.process_into_buffer(
+            &ZeroPadded::new(wave_in, self.nbr_channels),
+            wave_out,
+            active_channels_mask,
+        )?;
+        self.frame_counter.remove_padding(frames_in - frames, ratio);
+        Ok((frames_in, frames_out))
+    }
+
+    fn output_frames_remaining(&self) -> usize {
+        self.frame_counter.remaining(self.output_delay())
+    }
+
+    fn flush_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        flush_resampler(
+            self,
+            |resampler| resampler.frame_counter,
+            wave_in,
+            wave_out,
+            active_channels_mask,
+        )
+    }
+
     fn nbr_channels(&self) -> usize {
         self.nbr_channels
     }
@@ -491,7 +606,12 @@ where
         self.set_resample_ratio(new_ratio, ramp)
     }
 
+    fn max_resample_ratio_relative(&self) -> f64 {
+        self.max_relative_ratio
+    }
+
     fn reset(&mut self) {
+        self.frame_counter.reset();
         self.buffer
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
@@ -548,20 +668,99 @@ where
             buffer,
             interpolation: interpolation_type,
             channel_mask,
+            frame_counter: FrameCounter::default(),
         })
     }
+
+    /// Take a snapshot of the internal state of the resampler,
+    /// see [AsyncResamplerState] for details.
+    pub fn state(&self) -> AsyncResamplerState<T> {
+        AsyncResamplerState {
+            chunk_size: self.chunk_size,
+            last_index: self.last_index,
+            resample_ratio: self.resample_ratio,
+            target_ratio: self.target_ratio,
+            needed_input_size: self.needed_input_size,
+            buffer_fill: self.current_buffer_fill,
+            buffer: self.buffer.clone(),
+            output_frames_expected: self.frame_counter.expected,
+            output_frames_produced: self.frame_counter.produced,
+        }
+    }
+
+    /// Restore the internal state from a snapshot taken with [state](FastFixedOut::state).
+    /// The resampler must have been created with the same parameters
+    /// as the one the snapshot was taken from.
+    /// If the snapshot does not match, an error is returned and the state is left unchanged.
+    pub fn set_state(&mut self, state: &AsyncResamplerState<T>) -> ResampleResult<()> {
+        validate_state_buffer(&self.buffer, &state.buffer)?;
+        if state.chunk_size != self.chunk_size {
+            return Err(ResampleError::IncompatibleState);
+        }
+        validate_state_ratio(
+            state.resample_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+        )?;
+        validate_state_ratio(
+            state.target_ratio,
+            self.resample_ratio_original,
+            self.max_relative_ratio,
+        )?;
+        // The indices of the next chunk must not read outside the buffer.
+        validate_state_index(
+            state.last_index,
+            3.0 - 2.0 * POLYNOMIAL_LEN_U as f64
+                - first_index_step(
+                    state.resample_ratio,
+                    state.target_ratio,
+                    state.chunk_size as f64,
+                ),
+            self.buffer[0].len() as f64
+                - 2.0 * POLYNOMIAL_LEN_U as f64
+                - 5.0
+                - total_index_step(state.resample_ratio, state.target_ratio, state.chunk_size),
+        )?;
+        validate_state_frames(state.output_frames_expected)?;
+        // The new input and the frames kept for the next chunk must fit in the buffer.
+        let buffer_len = self.buffer[0].len();
+        if state.needed_input_size + 2 * POLYNOMIAL_LEN_U > buffer_len
+            || state.buffer_fill + 2 * POLYNOMIAL_LEN_U > buffer_len
+        {
+            return Err(ResampleError::IncompatibleState);
+        }
+        self.chunk_size = state.chunk_size;
+        self.last_index = state.last_index;
+        self.resample_ratio = state.resample_ratio;
+        self.target_ratio = state.target_ratio;
+        self.needed_input_size = state.needed_input_size;
+        self.current_buffer_fill = state.buffer_fill;
+        for (buf, saved) in self.buffer.iter_mut().zip(state.buffer.iter()) {
+            buf.copy_from_slice(saved);
+        }
+        self.frame_counter = FrameCounter {
+            expected: state.output_frames_expected,
+            produced: state.output_frames_produced,
+            ..Default::default()
+        };
+        Ok(())
+    }
 }
 
 impl<T> Resampler<T> for FastFixedOut<T>
 where
     T: Sample,
 {
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    fn process_into_buffer<Vin, Vout>(
         &mut self,
-        wave_in: &[Vin],
-        wave_out: &mut [Vout],
+        wave_in: &Vin,
+        wave_out: &mut Vout,
         active_channels_mask: Option<&[bool]>,
-    ) -> ResampleResult<(usize, usize)> {
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -576,6 +775,7 @@ where
             self.needed_input_size,
             self.chunk_size,
         )?;
+
         for buf in self.buffer.iter_mut() {
             buf.copy_within(
                 self.current_buffer_fill..self.current_buffer_fill + 2 * POLYNOMIAL_LEN_U,
@@ -584,14 +784,15 @@ where
         }
         self.current_buffer_fill = self.needed_input_size;
 
-        for (chan, wave_in) in wave_in
-            .iter()
-            .enumerate()
-            .filter(|(chan, _)| self.channel_mask[*chan])
-        {
-            debug_assert!(self.chunk_size <= wave_out[chan].as_mut().len());
-            self.buffer[chan][2 * POLYNOMIAL_LEN_U..2 * POLYNOMIAL_LEN_U + self.needed_input_size]
-                .copy_from_slice(&wave_in.as_ref()[..self.needed_input_size]);
+        for (chan, active) in self.channel_mask.iter().enumerate() {
+            if *active {
+                wave_in.copy_from_channel_to_slice(
+                    chan,
+                    0,
+                    &mut self.buffer[chan]
+                        [2 * POLYNOMIAL_LEN_U..2 * POLYNOMIAL_LEN_U + self.needed_input_size],
+                );
+            }
         }
 
         let mut idx = self.last_index;
@@ -615,10 +816,7 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 8) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(frame) = interp_septic(frac_offset, buf);
+                                wave_out.write_sample(chan, frame, interp_septic(frac_offset, buf));
                             }
                         }
                     }
@@ -639,10 +837,11 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 6) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(frame) = interp_quintic(frac_offset, buf);
+                                wave_out.write_sample(
+                                    chan,
+                                    frame,
+                                    interp_quintic(frac_offset, buf),
+                                );
                             }
                         }
                     }
@@ -663,10 +862,7 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 4) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(frame) = interp_cubic(frac_offset, buf);
+                                wave_out.write_sample(chan, frame, interp_cubic(frac_offset, buf));
                             }
                         }
                     }
@@ -687,10 +883,7 @@ where
                                     (start_idx + 2 * POLYNOMIAL_LEN_I) as usize
                                         ..(start_idx + 2 * POLYNOMIAL_LEN_I + 2) as usize,
                                 );
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(frame) = interp_lin(frac_offset, buf);
+                                wave_out.write_sample(chan, frame, interp_lin(frac_offset, buf));
                             }
                         }
                     }
@@ -708,10 +901,7 @@ where
                                     .buffer
                                     .get_unchecked(chan)
                                     .get_unchecked((start_idx + 2 * POLYNOMIAL_LEN_I) as usize);
-                                *wave_out
-                                    .get_unchecked_mut(chan)
-                                    .as_mut()
-                                    .get_unchecked_mut(frame) = *point;
+                                wave_out.write_sample(chan, frame, *point);
                             }
                         }
                     }
@@ -721,6 +911,11 @@ where
 
         // Store last index for next iteration.
         let input_frames_used = self.needed_input_size;
+        self.frame_counter.add(
+            input_frames_used,
+            self.chunk_size,
+            0.5 * (self.resample_ratio + self.target_ratio),
+        );
         self.last_index = idx - self.current_buffer_fill as f64;
         self.resample_ratio = self.target_ratio;
         self.needed_input_size = (self.last_index as f32
@@ -765,6 +960,51 @@ where
         (POLYNOMIAL_LEN_U as f64 * self.resample_ratio / 2.0) as usize
     }
 
+    fn process_partial_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let frames = partial_input_frames(wave_in, self.input_frames_next());
+        // The same average ratio as used when counting the chunk.
+        let ratio = 0.5 * (self.resample_ratio + self.target_ratio);
+        let (frames_in, frames_out) = self.process_into_buffer(
+            &ZeroPadded::new(wave_in, self.nbr_channels),
+            wave_out,
+            active_channels_mask,
+        )?;
+        self.frame_counter.remove_padding(frames_in - frames, ratio);
+        Ok((frames_in, frames_out))
+    }
+
+    fn output_frames_remaining(&self) -> usize {
+        self.frame_counter.remaining(self.output_delay())
+    }
+
+    fn flush_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        flush_resampler(
+            self,
+            |resampler| resampler.frame_counter,
+            wave_in,
+            wave_out,
+            active_channels_mask,
+        )
+    }
+
     fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> ResampleResult<()> {
         trace!("Change resample ratio to {}", new_ratio);
         if (new_ratio / self.resample_ratio_original >= 1.0 / self.max_relative_ratio)
@@ -794,7 +1034,12 @@ where
         self.set_resample_ratio(new_ratio, ramp)
     }
 
+    fn max_resample_ratio_relative(&self) -> f64 {
+        self.max_relative_ratio
+    }
+
     fn reset(&mut self) {
+        self.frame_counter.reset();
         self.buffer
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
@@ -812,9 +1057,9 @@ where
 #[cfg(test)]
 mod tests {
     use crate::PolynomialDegree;
-    use crate::Resampler;
-    use crate::{check_output, check_ratio};
+    use crate::{check_corrupt_state, check_output, check_ratio, check_state};
     use crate::{FastFixedIn, FastFixedOut};
+    use crate::{ResampleError, Resampler};
     use rand::Rng;
     use test_log::test;
 
@@ -908,14 +1153,9 @@ mod tests {
     #[test]
     fn make_resampler_fi_downsample() {
         // Replicate settings from reported issue.
-        let mut resampler = FastFixedIn::<f64>::new(
-            16000 as f64 / 96000 as f64,
-            1.0,
-            PolynomialDegree::Cubic,
-            1024,
-            2,
-        )
-        .unwrap();
+        let mut resampler =
+            FastFixedIn::<f64>::new(16000.0 / 96000.0, 1.0, PolynomialDegree::Cubic, 1024, 2)
+                .unwrap();
         let waves = vec![vec![0.0f64; 1024]; 2];
         let out = resampler.process(&waves, None).unwrap();
         assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
@@ -940,14 +1180,9 @@ mod tests {
     #[test]
     fn make_resampler_fi_upsample() {
         // Replicate settings from reported issue.
-        let mut resampler = FastFixedIn::<f64>::new(
-            192000 as f64 / 44100 as f64,
-            1.0,
-            PolynomialDegree::Cubic,
-            1024,
-            2,
-        )
-        .unwrap();
+        let mut resampler =
+            FastFixedIn::<f64>::new(192000.0 / 44100.0, 1.0, PolynomialDegree::Cubic, 1024, 2)
+                .unwrap();
         let waves = vec![vec![0.0f64; 1024]; 2];
         let out = resampler.process(&waves, None).unwrap();
         assert_eq!(out.len(), 2, "Expected {} channels, got {}", 2, out.len());
@@ -982,6 +1217,46 @@ mod tests {
         assert_eq!(out[0].len(), 1024);
     }
 
+    #[test]
+    fn restore_state_fi() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        resampler.set_resample_ratio(1.25, true).unwrap();
+        let mut restored =
+            FastFixedIn::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        check_state!(resampler, restored);
+    }
+
+    #[test]
+    fn restore_corrupt_state() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(8.0, 2.0, PolynomialDegree::Septic, 64, 2).unwrap();
+        let mut restored =
+            FastFixedIn::<f64>::new(8.0, 2.0, PolynomialDegree::Septic, 64, 2).unwrap();
+        check_corrupt_state!(resampler, restored, 8.0);
+        let mut resampler =
+            FastFixedOut::<f64>::new(0.1, 2.0, PolynomialDegree::Septic, 64, 2).unwrap();
+        let mut restored =
+            FastFixedOut::<f64>::new(0.1, 2.0, PolynomialDegree::Septic, 64, 2).unwrap();
+        check_corrupt_state!(resampler, restored, 0.1);
+    }
+
+    #[test]
+    fn restore_state_fo() {
+        let mut resampler =
+            FastFixedOut::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        resampler.set_resample_ratio(1.25, true).unwrap();
+        let mut restored =
+            FastFixedOut::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap();
+        check_state!(resampler, restored);
+        let mut other =
+            FastFixedOut::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 512, 2).unwrap();
+        assert!(matches!(
+            other.set_state(&resampler.state()),
+            Err(ResampleError::IncompatibleState)
+        ));
+    }
+
     #[test]
     fn reset_resampler_fo() {
         let mut resampler =
@@ -1228,4 +1503,21 @@ mod tests {
             FastFixedIn::<f32>::new(ratio, 100.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
         check_ratio!(resampler, ratio, 1000);
     }
+
+    #[test]
+    fn interleaved_buffer_too_short() {
+        let mut resampler =
+            FastFixedIn::<f64>::new(1.0, 1.0, PolynomialDegree::Cubic, 256, 2).unwrap();
+        let wave_in = vec![0.0; 2 * 255];
+        let mut wave_out = vec![0.0; 2 * resampler.output_frames_max()];
+        let result = resampler.process_interleaved_into_buffer(&wave_in, &mut wave_out, None);
+        assert!(matches!(
+            result,
+            Err(ResampleError::InsufficientInputBufferSize {
+                channel: 0,
+                expected: 256,
+                actual: 255
+            })
+        ));
+    }
 }
@@ -0,0 +1,415 @@
+use crate::adapters::{AudioInput, AudioOutput, InputWindow, OutputWindow};
+use crate::error::{ResampleError, ResampleResult};
+use crate::{Resampler, Sample};
+
+/// A wrapper for a [Resampler] that accepts any number of input frames per call.
+///
+/// The input frames are collected in an internal buffer,
+/// and the wrapped resampler processes a chunk whenever the buffer holds enough frames for it.
+/// This is useful when the input comes from a source that delivers a varying number of frames,
+/// for example the callback of an audio API, and works with all resampler types.
+///
+/// The internal buffer is allocated when the wrapper is created,
+/// with room for a given maximum number of frames.
+/// Processing does not allocate.
+///
+/// ```
+/// use rubato::{BufferedResampler, FastFixedIn, PolynomialDegree, Resampler};
+/// let resampler =
+///     FastFixedIn::<f64>::new(48000.0 / 44100.0, 1.0, PolynomialDegree::Cubic, 1024, 2).unwrap();
+/// let mut buffered = BufferedResampler::new(resampler, 4096);
+/// let mut wave_out = vec![vec![0.0; 8192]; 2];
+/// for frames in [100, 700, 1500, 3] {
+///     let wave_in = vec![vec![0.0; frames]; 2];
+///     let (frames_in, frames_out) = buffered.process_into_buffer(&wave_in, &mut wave_out, None).unwrap();
+///     assert_eq!(frames_in, frames);
+///     // The first `frames_out` frames of `wave_out` now hold new output.
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BufferedResampler<T, R> {
+    resampler: R,
+    buffer: Vec<Vec<T>>,
+    buffered_frames: usize,
+}
+
+impl<T, R> BufferedResampler<T, R>
+where
+    T: Sample,
+    R: Resampler<T>,
+{
+    /// Wrap a resampler, with an internal buffer with room for `max_buffered_frames` input frames.
+    /// The buffer is made at least large enough to hold the largest chunk the resampler can need,
+    /// as given by [input_frames_max](Resampler::input_frames_max).
+    pub fn new(resampler: R, max_buffered_frames: usize) -> Self {
+        let capacity = max_buffered_frames.max(resampler.input_frames_max());
+        let buffer = vec![vec![T::zero(); capacity]; resampler.nbr_channels()];
+        BufferedResampler {
+            resampler,
+            buffer,
+            buffered_frames: 0,
+        }
+    }
+
+    /// Process any number of input frames, and write the output frames that are ready
+    /// to a pre-allocated output buffer.
+    ///
+    /// The input frames are added to the internal buffer,
+    /// and the wrapped resampler then processes as many chunks as possible.
+    /// A chunk is only processed when the output buffer has room for
+    /// [output_frames_next](Resampler::output_frames_next) more frames.
+    /// The frames that are not processed stay in the internal buffer until the next call.
+    ///
+    /// The number of input frames is given by the shortest active channel.
+    /// If these do not all fit in the internal buffer, only the frames that fit are read.
+    /// The number of input frames read and output frames written are returned.
+    /// The caller must pass any input frames that were not read again in the next call.
+    pub fn process_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: &Vin,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let channels = self.resampler.nbr_channels();
+        if wave_in.nbr_channels() != channels {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: channels,
+                actual: wave_in.nbr_channels(),
+            });
+        }
+        validate_output(
+            self.resampler.nbr_channels(),
+            wave_out,
+            active_channels_mask,
+        )?;
+        let is_active = |chan: &usize| active_channels_mask.map(|mask| mask[*chan]).unwrap_or(true);
+        let frames_in = (0..channels)
+            .filter(is_active)
+            .map(|chan| wave_in.nbr_frames(chan))
+            .min()
+            .unwrap_or(0);
+        let room = output_room(wave_out, active_channels_mask);
+
+        let mut frames_read = 0;
+        let mut frames_written = 0;
+        loop {
+            let capacity = self.buffer.first().map(|chan| chan.len()).unwrap_or(0);
+            let frames = (frames_in - frames_read).min(capacity - self.buffered_frames);
+            if frames > 0 {
+                for (chan, chan_buf) in self.buffer.iter_mut().enumerate() {
+                    if is_active(&chan) {
+                        wave_in.copy_from_channel_to_slice(
+                            chan,
+                            frames_read,
+                            &mut chan_buf[self.buffered_frames..self.buffered_frames + frames],
+                        );
+                    }
+                }
+                self.buffered_frames += frames;
+                frames_read += frames;
+            }
+            if !self.can_process(room - frames_written) {
+                break;
+            }
+            frames_written +=
+                self.process_chunk(wave_out, frames_written, room, active_channels_mask)?;
+        }
+        Ok((frames_read, frames_written))
+    }
+
+    /// Flush the wrapper and the wrapped resampler at the end of a stream,
+    /// and write the remaining output frames to a pre-allocated output buffer.
+    ///
+    /// All frames in the internal buffer are processed,
+    /// and the resampler is then flushed with [Resampler::flush_into_buffer].
+    /// The output buffer must have room for all the remaining frames,
+    /// otherwise a [ResampleError] is returned.
+    /// This is checked before anything is processed, using an upper bound
+    /// of the number of remaining frames, so that a failed call can be retried
+    /// with a larger output buffer without losing any frames.
+    /// The number of frames written is returned.
+    ///
+    /// After flushing, the wrapper must be [reset](BufferedResampler::reset)
+    /// before it is used for a new stream.
+    pub fn flush_into_buffer<Vout>(
+        &mut self,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        validate_output(
+            self.resampler.nbr_channels(),
+            wave_out,
+            active_channels_mask,
+        )?;
+        let room = output_room(wave_out, active_channels_mask);
+        let needed = self.flush_frames_max();
+        if room < needed {
+            return Err(insufficient_output(wave_out, active_channels_mask, needed));
+        }
+        let mut frames_written = 0;
+        while self.buffered_frames >= self.resampler.input_frames_next() {
+            frames_written +=
+                self.process_chunk(wave_out, frames_written, room, active_channels_mask)?;
+        }
+        frames_written += self.resampler.flush_into_buffer(
+            Some(&InputWindow::new(&self.buffer, 0, self.buffered_frames)),
+            &mut OutputWindow::new(&mut *wave_out, frames_written, room),
+            active_channels_mask,
+        )?;
+        self.buffered_frames = 0;
+        Ok(frames_written)
+    }
+
+    /// Reset the wrapped resampler and discard the buffered input frames.
+    pub fn reset(&mut self) {
+        self.resampler.reset();
+        self.buffered_frames = 0;
+    }
+
+    /// Get the number of input frames currently held in the internal buffer.
+    pub fn buffered_frames(&self) -> usize {
+        self.buffered_frames
+    }
+
+    /// Get a reference to the wrapped resampler.
+    pub fn resampler(&self) -> &R {
+        &self.resampler
+    }
+
+    /// Get a mutable reference to the wrapped resampler,
+    /// for example for updating the resampling ratio.
+    pub fn resampler_mut(&mut self) -> &mut R {
+        &mut self.resampler
+    }
+
+    /// Unwrap the resampler. Any buffered input frames are discarded.
+    pub fn into_inner(self) -> R {
+        self.resampler
+    }
+
+    /// Check if the buffer holds enough frames for a chunk, and the output has room for the result.
+    fn can_process(&self, room: usize) -> bool {
+        self.buffered_frames >= self.resampler.input_frames_next()
+            && room >= self.resampler.output_frames_next()
+    }
+
+    /// Get an upper bound of the number of frames written when flushing.
+    /// The number of chunks is rounded up, since the input size of each chunk can vary,
+    /// and each chunk and the final flush are counted with the largest possible output.
+    fn flush_frames_max(&self) -> usize {
+        let frames_next = self.resampler.input_frames_next();
+        let remaining = self.resampler.output_frames_remaining();
+        if self.buffered_frames < frames_next {
+            return remaining + self.resampler.output_frames_max();
+        }
+        let chunks = (self.buffered_frames + frames_next - 1) / frames_next;
+        remaining + (chunks + 1) * self.resampler.output_frames_max()
+    }
+
+    /// Process one chunk from the internal buffer, writing the output from frame `offset`.
+    fn process_chunk<Vout>(
+        &mut self,
+        wave_out: &mut Vout,
+        offset: usize,
+        room: usize,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let (frames_in, frames_out) = self.resampler.process_into_buffer(
+            &self.buffer,
+            &mut OutputWindow::new(&mut *wave_out, offset, room),
+            active_channels_mask,
+        )?;
+        for chan_buf in self.buffer.iter_mut() {
+            chan_buf.copy_within(frames_in..self.buffered_frames, 0);
+        }
+        self.buffered_frames -= frames_in;
+        Ok(frames_out)
+    }
+}
+
+/// Get the number of frames that can be written to the output,
+/// given by the shortest active channel.
+pub(crate) fn output_room<T, Vout>(wave_out: &Vout, active_channels_mask: Option<&[bool]>) -> usize
+where
+    T: Sample,
+    Vout: AudioOutput<T> + ?Sized,
+{
+    (0..wave_out.nbr_channels())
+        .filter(|chan| active_channels_mask.map(|mask| mask[*chan]).unwrap_or(true))
+        .map(|chan| wave_out.nbr_frames(chan))
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
+/// Check that the output has the expected number of channels,
+/// and that the mask, if any, has one value per channel.
+pub(crate) fn validate_output<T, Vout>(
+    channels: usize,
+    wave_out: &Vout,
+    active_channels_mask: Option<&[bool]>,
+) -> ResampleResult<()>
+where
+    T: Sample,
+    Vout: AudioOutput<T> + ?Sized,
+{
+    if wave_out.nbr_channels() != channels {
+        return Err(ResampleError::WrongNumberOfOutputChannels {
+            expected: channels,
+            actual: wave_out.nbr_channels(),
+        });
+    }
+    if let Some(mask) = active_channels_mask {
+        if mask.len() != channels {
+            return Err(ResampleError::WrongNumberOfMaskChannels {
+                expected: channels,
+                actual: mask.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Make the error for an output buffer with room for fewer than `expected` frames.
+pub(crate) fn insufficient_output<T, Vout>(
+    wave_out: &Vout,
+    active_channels_mask: Option<&[bool]>,
+    expected: usize,
+) -> ResampleError
+where
+    T: Sample,
+    Vout: AudioOutput<T> + ?Sized,
+{
+    let (channel, actual) = (0..wave_out.nbr_channels())
+        .filter(|chan| active_channels_mask.map(|mask| mask[*chan]).unwrap_or(true))
+        .map(|chan| (chan, wave_out.nbr_frames(chan)))
+        .min_by_key(|(_, frames)| *frames)
+        .unwrap_or((0, 0));
+    ResampleError::InsufficientOutputBufferSize {
+        channel,
+        expected,
+        actual,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{reference_output, sine_stream};
+    use crate::{BufferedResampler, FastFixedOut, PolynomialDegree, ResampleError, Resampler};
+    use crate::{SincFixedIn, SincInterpolationParameters};
+    use test_log::test;
+
+    /// Feed a stream in chunks of varying size to a buffered resampler,
+    /// and check that the output is identical to that of the same resampler
+    /// processing the stream in its own chunk sizes.
+    fn check_buffered<R: Resampler<f64> + Clone>(resampler: R) {
+        let frames = 10000;
+        let stream = sine_stream(frames);
+        let expected = reference_output(resampler.clone(), &stream);
+
+        let mut buffered = BufferedResampler::new(resampler, 3000);
+        let mut output = Vec::new();
+        let mut wave_out = vec![vec![0.0; 5000]; 2];
+        let mut pos = 0;
+        for size in [1, 100, 333, 7, 2000, 1500, 64].iter().cycle() {
+            if pos >= frames {
+                break;
+            }
+            let chunk = &stream[pos..frames.min(pos + size)];
+            let (frames_in, frames_out) = buffered
+                .process_into_buffer(&[chunk, chunk], &mut wave_out, None)
+                .unwrap();
+            assert_eq!(frames_in, chunk.len());
+            assert_eq!(wave_out[0][..frames_out], wave_out[1][..frames_out]);
+            output.extend_from_slice(&wave_out[0][..frames_out]);
+            pos += frames_in;
+        }
+        let frames_out = buffered.flush_into_buffer(&mut wave_out, None).unwrap();
+        output.extend_from_slice(&wave_out[0][..frames_out]);
+        assert_eq!(buffered.buffered_frames(), 0);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn buffered_sinc() {
+        let params = SincInterpolationParameters::draft();
+        check_buffered(SincFixedIn::<f64>::new(1.2, 1.0, params, 1024, 2).unwrap());
+    }
+
+    #[test]
+    fn buffered_fast_fixed_out() {
+        check_buffered(
+            FastFixedOut::<f64>::new(0.7, 1.0, PolynomialDegree::Cubic, 512, 2).unwrap(),
+        );
+    }
+
+    #[cfg(feature = "fft_resampler")]
+    #[test]
+    fn buffered_fft() {
+        check_buffered(crate::FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap());
+    }
+
+    #[test]
+    fn buffered_limits() {
+        let resampler =
+            FastFixedOut::<f64>::new(1.0, 1.0, PolynomialDegree::Linear, 256, 1).unwrap();
+        let mut buffered = BufferedResampler::new(resampler, 1000);
+        // The output buffer is too small for a chunk, so the input is only buffered,
+        // and only the frames that fit in the buffer are read.
+        let wave_in = vec![vec![0.0; 1500]];
+        let mut wave_out = vec![vec![0.0; 100]];
+        let (frames_in, frames_out) = buffered
+            .process_into_buffer(&wave_in, &mut wave_out, None)
+            .unwrap();
+        assert_eq!((frames_in, frames_out), (1000, 0));
+        assert_eq!(buffered.buffered_frames(), 1000);
+        assert!(buffered.flush_into_buffer(&mut wave_out, None).is_err());
+
+        // With room in the output for three chunks of 256 frames, these are processed,
+        // and the buffer then has room for more input.
+        let mut wave_out = vec![vec![0.0; 1000]];
+        let (frames_in, frames_out) = buffered
+            .process_into_buffer(&wave_in[..], &mut wave_out, None)
+            .unwrap();
+        assert_eq!(frames_out, 768);
+        assert!(frames_in > 700 && frames_in < 800);
+        assert_eq!(buffered.buffered_frames(), 1000);
+    }
+
+    #[test]
+    fn buffered_flush_retry() {
+        let resampler =
+            FastFixedOut::<f64>::new(1.0, 1.0, PolynomialDegree::Linear, 256, 1).unwrap();
+        let mut buffered = BufferedResampler::new(resampler, 1000);
+        let wave_in = vec![vec![0.5; 1000]];
+        let mut wave_out = vec![vec![0.0; 100]];
+        buffered
+            .process_into_buffer(&wave_in, &mut wave_out, None)
+            .unwrap();
+        let mut reference = buffered.clone();
+
+        // The output has room for some of the chunks, but not for all remaining frames.
+        // Nothing is processed, so the flush can be retried with a larger output buffer.
+        let mut wave_out = vec![vec![0.0; 600]];
+        assert!(matches!(
+            buffered.flush_into_buffer(&mut wave_out, None),
+            Err(ResampleError::InsufficientOutputBufferSize { .. })
+        ));
+        assert_eq!(buffered.buffered_frames(), 1000);
+        let mut wave_out = vec![vec![0.0; 5000]];
+        let frames_out = buffered.flush_into_buffer(&mut wave_out, None).unwrap();
+        let mut expected = vec![vec![0.0; 5000]];
+        let expected_frames = reference.flush_into_buffer(&mut expected, None).unwrap();
+        assert_eq!(frames_out, expected_frames);
+        assert_eq!(wave_out[0][..frames_out], expected[0][..frames_out]);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::adapters::{AudioInput, AudioOutput};
+use crate::pcm::{Dither, PcmOutput, PcmSample};
+use crate::Sample;
+
+/// The probability density function of the dither noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DitherNoise {
+    /// No noise is added, the values are only rounded.
+    None,
+    /// Rectangular probability density function, with a peak amplitude of 0.5 LSB.
+    /// This removes the distortion, but leaves the noise level modulated by the signal.
+    Rectangular,
+    /// Triangular probability density function (TPDF), with a peak amplitude of 1 LSB.
+    /// This removes both the distortion and the noise modulation,
+    /// at the cost of a slightly higher noise level than rectangular dither.
+    Triangular,
+}
+
+/// The noise shaping filter used to move the quantization noise away from
+/// the frequencies where the ear is most sensitive.
+///
+/// The filters are designed for sample rates of 44.1 or 48 kHz.
+/// They increase the total noise power, but lower the audibility of the noise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseShaping {
+    /// No noise shaping, the noise spectrum is flat.
+    None,
+    /// First order highpass shaping, moving the noise towards high frequencies.
+    FirstOrder,
+    /// The five-tap filter by Lipshitz et al., loosely following the hearing threshold.
+    Lipshitz,
+    /// The nine-tap F-weighted filter by Wannamaker.
+    FWeighted,
+}
+
+impl NoiseShaping {
+    fn coefficients(&self) -> &'static [f64] {
+        match self {
+            NoiseShaping::None => &[],
+            NoiseShaping::FirstOrder => &[1.0],
+            NoiseShaping::Lipshitz => &[2.033, -2.165, 1.959, -1.590, 0.6149],
+            NoiseShaping::FWeighted => &[
+                2.412, -3.370, 3.937, -4.174, 3.353, -2.205, 1.281, -0.569, 0.0847,
+            ],
+        }
+    }
+}
+
+/// A small xorshift random number generator.
+/// It is not suitable for anything but generating noise,
+/// but is fast and gives the same sequence for a given seed on all platforms.
+#[derive(Debug, Clone)]
+struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    fn new(seed: u64) -> Self {
+        // Scramble the seed with a round of splitmix64, to avoid the all-zero state.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self { state: z.max(1) }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Get a random value in the range 0.0 to 1.0.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// The state of the noise shaping filter for one channel.
+#[derive(Debug, Clone)]
+struct ShapingState {
+    errors: Vec<f64>,
+    position: usize,
+}
+
+/// A quantizer that adds dither noise, and optionally shapes the spectrum of the noise,
+/// when reducing floating point values to integer samples.
+///
+/// The noise is generated by a deterministic random number generator,
+/// that gives the same sequence for a given seed.
+/// The state of the noise shaping filter is kept separately for each channel.
+///
+/// The quantizer can be used with [PcmOutput] to write the output of a resampler
+/// directly to an integer buffer,
+/// or to convert existing buffers with [process_into_buffer](Ditherer::process_into_buffer).
+#[derive(Debug, Clone)]
+pub struct Ditherer {
+    noise: DitherNoise,
+    coefficients: &'static [f64],
+    states: Vec<ShapingState>,
+    rng: XorShiftRng,
+    seed: u64,
+}
+
+impl Ditherer {
+    /// Create a new `Ditherer`.
+    ///
+    /// Parameters are:
+    /// - `channels`: Number of channels.
+    /// - `noise`: The type of dither noise to add.
+    /// - `shaping`: The noise shaping filter to use.
+    /// - `seed`: Seed for the random number generator.
+    pub fn new(channels: usize, noise: DitherNoise, shaping: NoiseShaping, seed: u64) -> Self {
+        let coefficients = shaping.coefficients();
+        Self {
+            noise,
+            coefficients,
+            states: vec![
+                ShapingState {
+                    errors: vec![0.0; coefficients.len()],
+                    position: 0,
+                };
+                channels
+            ],
+            rng: XorShiftRng::new(seed),
+            seed,
+        }
+    }
+
+    /// Get the number of channels this `Ditherer` was created for.
+    pub fn nbr_channels(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Reset the noise shaping filters and restart the random number generator.
+    pub fn reset(&mut self) {
+        for state in self.states.iter_mut() {
+            state.errors.iter_mut().for_each(|e| *e = 0.0);
+            state.position = 0;
+        }
+        self.rng = XorShiftRng::new(self.seed);
+    }
+
+    /// Convert the first `frames` frames of a floating point buffer to integer samples,
+    /// using this `Ditherer` for quantization.
+    /// The input and output are both channel-wise, for example `Vec<Vec<f64>>` and `Vec<Vec<i16>>`.
+    /// The values are scaled and clipped in the same way as by [PcmOutput].
+    /// Returns the number of clipped samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input or output has fewer than `frames` frames,
+    /// or if the numbers of channels of the input, output and `Ditherer` differ.
+    pub fn process_into_buffer<T, P, Vin, Vout>(
+        &mut self,
+        wave_in: &Vin,
+        wave_out: &mut Vout,
+        frames: usize,
+    ) -> usize
+    where
+        T: Sample,
+        P: PcmSample,
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<P> + ?Sized,
+    {
+        let channels = self.nbr_channels();
+        assert_eq!(
+            wave_in.nbr_channels(),
+            channels,
+            "Wrong number of input channels"
+        );
+        assert_eq!(
+            wave_out.nbr_channels(),
+            channels,
+            "Wrong number of output channels"
+        );
+        let mut output = PcmOutput::with_dither(wave_out, &mut *self);
+        for chan in 0..channels {
+            for frame in 0..frames {
+                let value: T = wave_in.read_sample(chan, frame);
+                output.write_sample(chan, frame, value);
+            }
+        }
+        output.clipped_samples()
+    }
+
+    fn noise(&mut self) -> f64 {
+        match self.noise {
+            DitherNoise::None => 0.0,
+            DitherNoise::Rectangular => self.rng.next_f64() - 0.5,
+            DitherNoise::Triangular => self.rng.next_f64() - self.rng.next_f64(),
+        }
+    }
+}
+
+impl Dither for Ditherer {
+    /// Quantize a value for the given channel.
+    ///
+    /// The values of each channel must be given in order,
+    /// but the channels can be processed independently of each other.
+    fn quantize(&mut self, channel: usize, value: f64) -> f64 {
+        let noise = self.noise();
+        let state = &mut self.states[channel];
+        let taps = self.coefficients.len();
+        let mut shaped = value;
+        for (n, coeff) in self.coefficients.iter().enumerate() {
+            shaped -= coeff * state.errors[(state.position + taps - 1 - n) % taps];
+        }
+        let quantized = (shaped + noise).round();
+        if taps > 0 {
+            state.errors[state.position] = quantized - shaped;
+            state.position = (state.position + 1) % taps;
+        }
+        quantized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DitherNoise, Ditherer, NoiseShaping};
+    use crate::pcm::Dither;
+    use test_log::test;
+
+    /// Quantize a quiet sine, and return the quantization error.
+    fn quantization_error(ditherer: &mut Ditherer, length: usize) -> Vec<f64> {
+        (0..length)
+            .map(|n| {
+                let value = 3.3 * (n as f64 * 0.01).sin();
+                ditherer.quantize(0, value) - value
+            })
+            .collect()
+    }
+
+    /// Power of the error in the frequency band below 0.1 times the sample rate,
+    /// calculated with a plain DFT.
+    fn low_band_power(error: &[f64]) -> f64 {
+        let length = error.len();
+        (1..length / 10)
+            .map(|bin| {
+                let (re, im) = error
+                    .iter()
+                    .enumerate()
+                    .fold((0.0, 0.0), |(re, im), (n, e)| {
+                        let phase = 2.0 * std::f64::consts::PI * (bin * n) as f64 / length as f64;
+                        (re + e * phase.cos(), im - e * phase.sin())
+                    });
+                re * re + im * im
+            })
+            .sum()
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut first = Ditherer::new(1, DitherNoise::Triangular, NoiseShaping::Lipshitz, 123);
+        let mut second = Ditherer::new(1, DitherNoise::Triangular, NoiseShaping::Lipshitz, 123);
+        let mut other = Ditherer::new(1, DitherNoise::Triangular, NoiseShaping::Lipshitz, 124);
+        let error_first = quantization_error(&mut first, 1000);
+        assert_eq!(error_first, quantization_error(&mut second, 1000));
+        assert_ne!(error_first, quantization_error(&mut other, 1000));
+        first.reset();
+        assert_eq!(error_first, quantization_error(&mut first, 1000));
+    }
+
+    #[test]
+    fn dither_removes_bias() {
+        for noise in [DitherNoise::Rectangular, DitherNoise::Triangular] {
+            let mut ditherer = Ditherer::new(1, noise, NoiseShaping::None, 0);
+            let length = 100000;
+            let mean = (0..length).map(|_| ditherer.quantize(0, 0.3)).sum::<f64>() / length as f64;
+            assert!((mean - 0.3).abs() < 0.01, "{:?}: mean {}", noise, mean);
+        }
+        let mut ditherer = Ditherer::new(1, DitherNoise::None, NoiseShaping::None, 0);
+        assert_eq!(ditherer.quantize(0, 0.3), 0.0);
+    }
+
+    #[test]
+    fn triangular_noise_range() {
+        let mut ditherer = Ditherer::new(1, DitherNoise::Triangular, NoiseShaping::None, 0);
+        let error = quantization_error(&mut ditherer, 10000);
+        assert!(error.iter().all(|e| e.abs() <= 1.5));
+    }
+
+    #[test]
+    fn noise_shaping_lowers_low_frequency_noise() {
+        let mut flat = Ditherer::new(1, DitherNoise::Triangular, NoiseShaping::None, 0);
+        let flat_power = low_band_power(&quantization_error(&mut flat, 2000));
+        for shaping in [
+            NoiseShaping::FirstOrder,
+            NoiseShaping::Lipshitz,
+            NoiseShaping::FWeighted,
+        ] {
+            let mut shaped = Ditherer::new(1, DitherNoise::Triangular, shaping, 0);
+            let error = quantization_error(&mut shaped, 2000);
+            assert!(
+                error.iter().all(|e| e.abs() < 50.0),
+                "{:?} unstable",
+                shaping
+            );
+            let shaped_power = low_band_power(&error);
+            assert!(
+                shaped_power < 0.25 * flat_power,
+                "{:?}: {} vs {}",
+                shaping,
+                shaped_power,
+                flat_power
+            );
+        }
+    }
+
+    #[test]
+    fn channels_are_independent() {
+        let mut ditherer = Ditherer::new(2, DitherNoise::None, NoiseShaping::FirstOrder, 0);
+        // The first order shaper feeds back the error of the previous sample.
+        assert_eq!(ditherer.quantize(0, 0.4), 0.0);
+        assert_eq!(ditherer.quantize(1, 0.0), 0.0);
+        assert_eq!(ditherer.quantize(0, 0.4), 1.0);
+        assert_eq!(ditherer.quantize(1, 0.0), 0.0);
+    }
+
+    #[test]
+    fn process_buffers() {
+        let wave_in = vec![vec![0.5, -1.5, 0.25], vec![0.0, 1.0, -0.5]];
+        let mut wave_out = vec![vec![0i16; 3]; 2];
+        let mut ditherer = Ditherer::new(2, DitherNoise::None, NoiseShaping::None, 0);
+        let clipped = ditherer.process_into_buffer(&wave_in, &mut wave_out, 3);
+        assert_eq!(clipped, 2);
+        assert_eq!(wave_out, [[16384, -32768, 8192], [0, 32767, -16384]]);
+    }
+}
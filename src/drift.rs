@@ -0,0 +1,464 @@
+use crate::error::ResampleResult;
+use crate::{Resampler, Sample};
+use std::marker::PhantomData;
+
+/// Parameters for a [DriftController].
+///
+/// The controller is a PI controller, that adjusts the relative resampling ratio to
+/// `1 - proportional_gain * error - integral_gain * sum_of_errors`,
+/// where the error is the difference between the measured and the target buffer level, in frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriftControllerParameters {
+    /// The wanted level of the buffer, in frames.
+    pub target_level: f64,
+    /// The change of the relative ratio for each frame of difference between the measured and target levels.
+    pub proportional_gain: f64,
+    /// The change of the relative ratio for each frame of the accumulated difference
+    /// between the measured and target levels.
+    pub integral_gain: f64,
+    /// The maximum relative ratio. The ratio is kept between `1 / max_relative_ratio`
+    /// and `max_relative_ratio`, and is further limited to the maximum of the resampler,
+    /// see [Resampler::max_resample_ratio_relative].
+    pub max_relative_ratio: f64,
+    /// Ramp the ratio smoothly to each new value, see [Resampler::set_resample_ratio].
+    pub ramp: bool,
+}
+
+impl DriftControllerParameters {
+    /// Parameters for a controller that is updated once for every `update_frames` frames,
+    /// with the buffer level measured in the same frames.
+    ///
+    /// The gains give a critically damped control loop, without overshoot,
+    /// with a time constant of about 1000 updates.
+    /// The relative ratio is limited to 1.01, and ramping is enabled.
+    pub fn new(target_level: f64, update_frames: usize) -> Self {
+        let frames = update_frames.max(1) as f64;
+        DriftControllerParameters {
+            target_level,
+            proportional_gain: 2.0e-3 / frames,
+            integral_gain: 1.0e-6 / frames,
+            max_relative_ratio: 1.01,
+            ramp: true,
+        }
+    }
+}
+
+/// A controller that compensates for clock drift, by adjusting the ratio of a resampler
+/// to keep the level of a buffer constant.
+///
+/// This is used when the input and output of the resampler run on different clocks,
+/// for example when recording with one sound card and playing back on another.
+/// The two clocks never run at exactly the same rate, so a fixed ratio would slowly
+/// make the buffer between them run empty, or overflow.
+///
+/// Once per processing cycle, the level of the buffer is measured and given to
+/// [update](DriftController::update).
+/// The buffer may be either before the resampler, holding input frames waiting to be resampled,
+/// or after it, holding output frames waiting to be played.
+/// In both cases, a level above the target means that the ratio must be decreased.
+/// The controller updates the ratio of the wrapped asynchronous resampler
+/// with [Resampler::set_resample_ratio_relative].
+///
+/// ```
+/// use rubato::{DriftController, DriftControllerParameters, FastFixedOut, PolynomialDegree, Resampler};
+/// let resampler = FastFixedOut::<f32>::new(1.0, 1.1, PolynomialDegree::Cubic, 512, 2).unwrap();
+/// let params = DriftControllerParameters::new(2048.0, 512);
+/// let mut controller = DriftController::new(resampler, params);
+/// // In each cycle, measure the buffer level and update the controller.
+/// let relative_ratio = controller.update(2100.0).unwrap();
+/// assert!(relative_ratio < 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DriftController<T, R> {
+    resampler: R,
+    params: DriftControllerParameters,
+    integral: f64,
+    relative_ratio: f64,
+    phantom: PhantomData<T>,
+}
+
+impl<T, R> DriftController<T, R>
+where
+    T: Sample,
+    R: Resampler<T>,
+{
+    /// Wrap an asynchronous resampler in a drift controller.
+    pub fn new(resampler: R, params: DriftControllerParameters) -> Self {
+        DriftController {
+            resampler,
+            params,
+            integral: 0.0,
+            relative_ratio: 1.0,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Update the controller with a new measurement of the buffer level, in frames.
+    /// The ratio of the resampler is updated, and the new relative ratio is returned.
+    /// If the ratio can't be updated, for example for a synchronous resampler,
+    /// the error is returned and the controller is left unchanged.
+    pub fn update(&mut self, buffer_level: f64) -> ResampleResult<f64> {
+        let params = &self.params;
+        let error = buffer_level - params.target_level;
+        let max_ratio = params
+            .max_relative_ratio
+            .min(self.resampler.max_resample_ratio_relative());
+        let min_ratio = 1.0 / max_ratio;
+        let integral = self.integral + error;
+        let unclamped = 1.0 - params.proportional_gain * error - params.integral_gain * integral;
+        let relative_ratio = unclamped.clamp(min_ratio, max_ratio);
+        self.resampler
+            .set_resample_ratio_relative(relative_ratio, params.ramp)?;
+        // Only integrate while the ratio is within the limits, to avoid windup.
+        if unclamped == relative_ratio {
+            self.integral = integral;
+        }
+        self.relative_ratio = relative_ratio;
+        Ok(relative_ratio)
+    }
+
+    /// Get the current relative ratio.
+    pub fn relative_ratio(&self) -> f64 {
+        self.relative_ratio
+    }
+
+    /// Reset the controller and the wrapped resampler.
+    /// The relative ratio returns to 1.0.
+    pub fn reset(&mut self) {
+        self.resampler.reset();
+        self.integral = 0.0;
+        self.relative_ratio = 1.0;
+    }
+
+    /// Get a reference to the wrapped resampler.
+    pub fn resampler(&self) -> &R {
+        &self.resampler
+    }
+
+    /// Get a mutable reference to the wrapped resampler, for example for processing.
+    pub fn resampler_mut(&mut self) -> &mut R {
+        &mut self.resampler
+    }
+
+    /// Unwrap the resampler.
+    pub fn into_inner(self) -> R {
+        self.resampler
+    }
+}
+
+/// A delay-locked loop that estimates the actual sample rate of a clock from timestamps.
+#[derive(Debug, Clone)]
+struct ClockDll {
+    nominal_period: f64,
+    bandwidth: f64,
+    /// The estimated duration of one frame, in seconds.
+    period: f64,
+    /// The filtered time of the last update.
+    time: Option<f64>,
+}
+
+impl ClockDll {
+    fn new(sample_rate: usize, bandwidth: f64) -> Self {
+        let nominal_period = 1.0 / sample_rate as f64;
+        ClockDll {
+            nominal_period,
+            bandwidth,
+            period: nominal_period,
+            time: None,
+        }
+    }
+
+    /// Update with a block of frames and the time when it was delivered or consumed.
+    fn update(&mut self, frames: usize, timestamp: f64) {
+        match self.time {
+            None => self.time = Some(timestamp),
+            Some(_) if frames == 0 => {}
+            Some(time) => {
+                let frames = frames as f64;
+                let predicted = time + frames * self.period;
+                let error = timestamp - predicted;
+                let omega = 2.0 * std::f64::consts::PI * self.bandwidth * frames * self.period;
+                self.time = Some(predicted + std::f64::consts::SQRT_2 * omega * error);
+                self.period += omega * omega * error / frames;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.period = self.nominal_period;
+        self.time = None;
+    }
+}
+
+/// An estimator for the ratio between the actual sample rates of two clocks, from timestamps.
+///
+/// This is used for bridging two clock domains, when timestamps are available for the
+/// blocks of frames delivered by the input, and for those consumed by the output.
+/// For example, the callbacks of audio APIs often provide such timestamps.
+/// The timestamps are given in seconds, measured by a common reference clock such as the system clock.
+/// They may be jittery, since each side uses a delay-locked loop (DLL) to filter them,
+/// and estimate the actual sample rate.
+///
+/// The estimated [ratio](RateEstimator::ratio) is the ratio between the actual output and input
+/// sample rates, and is meant to be given to [Resampler::set_resample_ratio]
+/// of an asynchronous resampler.
+/// The loop bandwidth sets the trade-off between a fast response and a smooth ratio.
+/// A value between 0.1 and 1 Hz is suitable for most uses.
+///
+/// ```
+/// use rubato::RateEstimator;
+/// let mut estimator = RateEstimator::new(44100, 48000, 0.5);
+/// // Input blocks of 441 frames, from a clock that runs slightly fast.
+/// for n in 0..1000 {
+///     estimator.input_timestamp(441, n as f64 * 441.0 / 44105.0);
+///     estimator.output_timestamp(480, n as f64 * 480.0 / 48000.0);
+/// }
+/// assert!((estimator.ratio() - 48000.0 / 44105.0).abs() < 1.0e-6);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateEstimator {
+    input: ClockDll,
+    output: ClockDll,
+}
+
+impl RateEstimator {
+    /// Create a new estimator, for the given nominal input and output sample rates.
+    /// The `bandwidth` of the loops is given in Hz.
+    pub fn new(fs_in: usize, fs_out: usize, bandwidth: f64) -> Self {
+        RateEstimator {
+            input: ClockDll::new(fs_in, bandwidth),
+            output: ClockDll::new(fs_out, bandwidth),
+        }
+    }
+
+    /// Update the estimate with a block of `frames` frames delivered by the input,
+    /// at the time `timestamp` in seconds.
+    pub fn input_timestamp(&mut self, frames: usize, timestamp: f64) {
+        self.input.update(frames, timestamp);
+    }
+
+    /// Update the estimate with a block of `frames` frames consumed by the output,
+    /// at the time `timestamp` in seconds.
+    pub fn output_timestamp(&mut self, frames: usize, timestamp: f64) {
+        self.output.update(frames, timestamp);
+    }
+
+    /// Get the estimated actual sample rate of the input.
+    pub fn input_rate(&self) -> f64 {
+        1.0 / self.input.period
+    }
+
+    /// Get the estimated actual sample rate of the output.
+    pub fn output_rate(&self) -> f64 {
+        1.0 / self.output.period
+    }
+
+    /// Get the estimated ratio between the actual output and input sample rates.
+    pub fn ratio(&self) -> f64 {
+        self.input.period / self.output.period
+    }
+
+    /// Reset the estimator to the nominal sample rates.
+    /// This should be done when a clock has been interrupted,
+    /// for example after a buffer underrun.
+    pub fn reset(&mut self) {
+        self.input.reset();
+        self.output.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DriftController, DriftControllerParameters, RateEstimator};
+    use crate::{FastFixedIn, FastFixedOut, PolynomialDegree, PolyphaseFixedIn, Resampler};
+    use crate::{SincFixedIn, SincInterpolationParameters};
+    use test_log::test;
+
+    /// Simulate a source that runs `drift` faster than the sink, for 4000 cycles of 256 frames.
+    /// With `input_buffer`, the buffer is before the resampler, otherwise it is after it.
+    /// Returns the average relative ratio, and the min and max buffer levels, of the last 1000 cycles.
+    fn simulate<R: Resampler<f64>>(
+        resampler: R,
+        drift: f64,
+        input_buffer: bool,
+    ) -> (f64, f64, f64) {
+        let params = DriftControllerParameters {
+            proportional_gain: 2.0e-2 / 256.0,
+            integral_gain: 1.0e-4 / 256.0,
+            ..DriftControllerParameters::new(1000.0, 256)
+        };
+        let mut controller = DriftController::new(resampler, params);
+        let wave_in = vec![vec![0.0; controller.resampler().input_frames_max()]];
+        let mut wave_out = controller.resampler().output_buffer_allocate(true);
+        let mut level = 1000.0;
+        let (mut ratio_sum, mut min_level, mut max_level) = (0.0, f64::MAX, 0.0f64);
+        for cycle in 0..4000 {
+            let (frames_in, frames_out) = controller
+                .resampler_mut()
+                .process_into_buffer(&wave_in, &mut wave_out, None)
+                .unwrap();
+            if input_buffer {
+                level += 256.0 * (1.0 + drift) - frames_in as f64;
+            } else {
+                level += frames_out as f64 - 256.0 / (1.0 + drift);
+            }
+            let ratio = controller.update(level).unwrap();
+            if cycle >= 3000 {
+                ratio_sum += ratio;
+                min_level = min_level.min(level);
+                max_level = max_level.max(level);
+            }
+        }
+        (ratio_sum / 1000.0, min_level, max_level)
+    }
+
+    #[test]
+    fn drift_input_buffer() {
+        for drift in [-500.0e-6, 300.0e-6] {
+            let resampler =
+                FastFixedOut::<f64>::new(1.0, 1.1, PolynomialDegree::Linear, 256, 1).unwrap();
+            let (ratio, min_level, max_level) = simulate(resampler, drift, true);
+            assert!(
+                (ratio - 1.0 / (1.0 + drift)).abs() < 1.0e-6,
+                "ratio {}, drift {}",
+                ratio,
+                drift
+            );
+            assert!(min_level > 990.0 && max_level < 1010.0);
+        }
+    }
+
+    #[test]
+    fn drift_output_buffer() {
+        for drift in [-500.0e-6, 300.0e-6] {
+            let resampler =
+                FastFixedIn::<f64>::new(1.0, 1.1, PolynomialDegree::Linear, 256, 1).unwrap();
+            let (ratio, min_level, max_level) = simulate(resampler, drift, false);
+            assert!(
+                (ratio - 1.0 / (1.0 + drift)).abs() < 1.0e-6,
+                "ratio {}, drift {}",
+                ratio,
+                drift
+            );
+            assert!(min_level > 990.0 && max_level < 1010.0);
+        }
+    }
+
+    /// A simple pseudo-random timing jitter, uniform between -0.5 and +0.5 ms.
+    fn jitter(state: &mut u32) -> f64 {
+        *state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        (*state as f64 / u32::MAX as f64 - 0.5) * 1.0e-3
+    }
+
+    #[test]
+    fn rate_estimator_drifting_clocks() {
+        // The clocks deviate from their nominal rates by +0.3% and -0.2%,
+        // and the timestamps have jitter.
+        let fs_in = 44100.0 * 1.003;
+        let fs_out = 48000.0 * 0.998;
+        let mut estimator = RateEstimator::new(44100, 48000, 0.2);
+        let params = SincInterpolationParameters::draft();
+        let mut resampler =
+            SincFixedIn::<f64>::new(48000.0 / 44100.0, 1.1, params, 1024, 1).unwrap();
+        let wave_in = vec![vec![0.0; 1024]];
+        let mut wave_out = resampler.output_buffer_allocate(true);
+
+        // Input blocks of 441 frames are resampled in chunks of 1024 frames,
+        // and added to the output buffer. The output consumes blocks of 512 frames.
+        let mut random = 1;
+        let mut buffered_in = 0;
+        let mut level = 2048;
+        let mut levels = Vec::new();
+        let (mut time_in, mut time_out) = (0.0, 0.0);
+        while time_out < 20.0 {
+            if time_in <= time_out {
+                buffered_in += 441;
+                estimator.input_timestamp(441, time_in + jitter(&mut random));
+                while buffered_in >= resampler.input_frames_next() {
+                    let (frames_in, frames_out) = resampler
+                        .process_into_buffer(&wave_in, &mut wave_out, None)
+                        .unwrap();
+                    buffered_in -= frames_in;
+                    level += frames_out;
+                }
+                time_in += 441.0 / fs_in;
+            } else {
+                assert!(level >= 512, "buffer underrun at {} s", time_out);
+                level -= 512;
+                estimator.output_timestamp(512, time_out + jitter(&mut random));
+                resampler
+                    .set_resample_ratio(estimator.ratio(), true)
+                    .unwrap();
+                levels.push(level as f64);
+                time_out += 512.0 / fs_out;
+            }
+        }
+        assert!(
+            (estimator.ratio() - fs_out / fs_in).abs() < 50.0e-6,
+            "{} {}",
+            estimator.ratio(),
+            fs_out / fs_in
+        );
+
+        // The buffer level stays bounded. Without compensation,
+        // it would change by about 240 frames per second.
+        let mean = |levels: &[f64]| levels.iter().sum::<f64>() / levels.len() as f64;
+        let n = levels.len();
+        let late = mean(&levels[n - n / 4..]);
+        let middle = mean(&levels[n / 2..n - n / 4]);
+        assert!((late - middle).abs() < 50.0, "{} {}", late, middle);
+        let max = levels[n / 2..]
+            .iter()
+            .fold(0.0f64, |max, val| max.max(*val));
+        assert!(max < 3000.0);
+    }
+
+    #[test]
+    fn rate_estimator_reset() {
+        let mut estimator = RateEstimator::new(44100, 48000, 0.5);
+        for n in 0..5000 {
+            estimator.input_timestamp(100, n as f64 * 100.0 / 44000.0);
+            estimator.output_timestamp(100, n as f64 * 100.0 / 48000.0);
+        }
+        assert!((estimator.input_rate() - 44000.0).abs() < 0.01);
+        assert!((estimator.output_rate() - 48000.0).abs() < 0.01);
+        estimator.reset();
+        assert_eq!(estimator.ratio(), 48000.0 / 44100.0);
+    }
+
+    #[test]
+    fn drift_limited() {
+        let resampler =
+            FastFixedIn::<f64>::new(1.0, 1.1, PolynomialDegree::Linear, 256, 1).unwrap();
+        let params = DriftControllerParameters::new(1000.0, 256);
+        let mut controller = DriftController::new(resampler, params);
+        for _ in 0..100 {
+            assert_eq!(controller.update(100000.0).unwrap(), 1.0 / 1.01);
+        }
+        // The integral does not wind up while the ratio is limited.
+        assert!(controller.update(1000.0).unwrap() > 0.999);
+        controller.reset();
+        assert_eq!(controller.relative_ratio(), 1.0);
+
+        // The ratio is also limited to the maximum of the resampler.
+        let resampler =
+            FastFixedIn::<f64>::new(48000.0 / 44100.0, 1.001, PolynomialDegree::Linear, 256, 1)
+                .unwrap();
+        let mut controller = DriftController::new(resampler, params);
+        assert_eq!(controller.update(100000.0).unwrap(), 1.0 / 1.001);
+        assert_eq!(controller.update(-100000.0).unwrap(), 1.001);
+    }
+
+    #[test]
+    fn drift_sync_error() {
+        // A synchronous resampler can't change the ratio,
+        // and the failed update leaves the controller unchanged.
+        let params = SincInterpolationParameters::draft();
+        let resampler = PolyphaseFixedIn::<f64>::new(44100, 48000, params, 1024, 1).unwrap();
+        let mut controller =
+            DriftController::new(resampler, DriftControllerParameters::new(1000.0, 256));
+        assert!(controller.update(1100.0).is_err());
+        assert_eq!(controller.relative_ratio(), 1.0);
+        assert_eq!(controller.integral, 0.0);
+    }
+}
@@ -80,9 +80,46 @@ impl error::Error for MissingCpuFeature {}
 
 /// The error type returned when constructing [Resampler](crate::Resampler).
 pub enum ResamplerConstructionError {
-    InvalidSampleRate { input: usize, output: usize },
+    InvalidSampleRate {
+        input: usize,
+        output: usize,
+    },
     InvalidRelativeRatio(f64),
     InvalidRatio(f64),
+    /// The numerator and denominator of a resampling ratio given as a fraction must both be > 0.
+    InvalidRationalRatio {
+        numerator: u64,
+        denominator: u64,
+    },
+    /// The relative cutoff frequency of the antialiasing filter must be > 0 and <= 1.
+    InvalidCutoff(f32),
+    /// The filter specification is invalid.
+    /// The passband edge must be between 0 and 1,
+    /// and the stopband attenuation and passband ripple must be positive.
+    InvalidFilterSpecification {
+        passband_edge: f32,
+        stopband_attenuation: f64,
+        passband_ripple: f64,
+    },
+    /// The filter specification requires a longer sinc than the maximum allowed.
+    SincTooLong {
+        required: usize,
+        max: usize,
+    },
+    /// The oversampling factor is too low to reach the required attenuation
+    /// with the selected interpolation type.
+    /// The attenuations are given in dB.
+    InsufficientOversampling {
+        required: f64,
+        achievable: f64,
+    },
+    /// The resampling ratio and filter length require a larger FFT than the maximum allowed.
+    /// This happens when the numerator or denominator of the reduced ratio is very large,
+    /// or when the antialiasing filter is very long.
+    FftTooLong {
+        required: usize,
+        max: usize,
+    },
 }
 
 impl fmt::Display for ResamplerConstructionError {
@@ -94,9 +131,28 @@ impl fmt::Display for ResamplerConstructionError {
             Self::InvalidRatio(provided) => write!(formatter,
                 "Invalid resample_ratio provided: {}. resample_ratio must be > 0", provided
             ),
+            Self::InvalidRationalRatio{numerator, denominator} => write!(formatter,
+                "Invalid resampling ratio provided: {}/{}. Numerator and denominator must both be > 0", numerator, denominator
+            ),
+            Self::InvalidCutoff(provided) => write!(formatter,
+                "Invalid f_cutoff provided: {}. f_cutoff must be > 0 and <= 1", provided
+            ),
             Self::InvalidRelativeRatio(provided) => write!(formatter,
                 "Invalid max_resample_ratio_relative provided: {}. max_resample_ratio_relative must be >= 1", provided
             ),
+            Self::InvalidFilterSpecification{passband_edge, stopband_attenuation, passband_ripple} => write!(formatter,
+                "Invalid filter specification, passband edge: {}, stopband attenuation: {} dB, passband ripple: {} dB. The passband edge must be > 0 and < 1, attenuation and ripple must be > 0",
+                passband_edge, stopband_attenuation, passband_ripple
+            ),
+            Self::SincTooLong{required, max} => write!(formatter,
+                "The filter specification requires a sinc length of {}, larger than the maximum of {}", required, max
+            ),
+            Self::InsufficientOversampling{required, achievable} => write!(formatter,
+                "The oversampling factor is too low, the required attenuation is {:.1} dB but the interpolation only reaches {:.1} dB", required, achievable
+            ),
+            Self::FftTooLong{required, max} => write!(formatter,
+                "The resampling ratio and filter length require an FFT size of {}, larger than the maximum of {}", required, max
+            ),
         }
     }
 }
@@ -156,6 +212,15 @@ pub enum ResampleError {
         requested: usize,
     },
     ChunkSizeNotAdjustable,
+    /// Error raised when restoring a state snapshot that does not match the resampler,
+    /// for example because the resampler was created with different parameters.
+    IncompatibleState,
+    /// Error raised when flushing a resampler stops producing output frames
+    /// before all the remaining frames have been written.
+    FlushStalled {
+        expected: usize,
+        actual: usize,
+    },
 }
 
 impl fmt::Display for ResampleError {
@@ -225,6 +290,16 @@ impl fmt::Display for ResampleError {
             Self::ChunkSizeNotAdjustable { .. } => {
                 write!(f, "This resampler does not support changing the chunk size")
             }
+            Self::IncompatibleState => {
+                write!(f, "The state snapshot does not match this resampler")
+            }
+            Self::FlushStalled { expected, actual } => {
+                write!(
+                    f,
+                    "Flushing stopped making progress after {} of {} frames",
+                    actual, expected
+                )
+            }
         }
     }
 }
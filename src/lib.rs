@@ -35,29 +35,57 @@ macro_rules! error { ($($x:tt)*) => (
     }
 ) }
 
+mod adapters;
+mod analysis;
 mod asynchro_fast;
 mod asynchro_sinc;
+mod buffered;
+mod dither;
+mod drift;
 mod error;
 mod interpolation;
+mod oneshot;
+mod pcm;
+mod polyphase;
+mod ring;
 mod sample;
 mod sinc;
+mod state;
 #[cfg(feature = "fft_resampler")]
 mod synchro;
 mod windows;
 
 pub mod sinc_interpolator;
 
+pub use crate::adapters::{
+    AudioInput, AudioOutput, InterleavedSlice, SequentialSlice, StridedSlice,
+};
+use crate::adapters::{OutputWindow, ZeroPadded};
+pub use crate::analysis::{
+    analyze_sinc_filter, sinc_frequency_response, FilterAnalysis, ResponsePoint,
+};
 pub use crate::asynchro_fast::{FastFixedIn, FastFixedOut, PolynomialDegree};
 pub use crate::asynchro_sinc::{
     SincFixedIn, SincFixedOut, SincInterpolationParameters, SincInterpolationType,
 };
+pub use crate::buffered::BufferedResampler;
+pub use crate::dither::{DitherNoise, Ditherer, NoiseShaping};
+pub use crate::drift::{DriftController, DriftControllerParameters, RateEstimator};
 pub use crate::error::{
     CpuFeature, MissingCpuFeature, ResampleError, ResampleResult, ResamplerConstructionError,
 };
+pub use crate::oneshot::{resample_all, ResampleQuality};
+pub use crate::pcm::{Dither, NoDither, PcmInput, PcmOutput, PcmSample, I24};
+pub use crate::polyphase::PolyphaseFixedIn;
+pub use crate::ring::{ring_resampler, RingConsumer, RingProducer};
 pub use crate::sample::Sample;
+pub use crate::sinc::SincPhase;
+pub use crate::state::AsyncResamplerState;
 #[cfg(feature = "fft_resampler")]
-pub use crate::synchro::{FftFixedIn, FftFixedInOut, FftFixedOut};
-pub use crate::windows::{calculate_cutoff, WindowFunction};
+pub use crate::state::FftResamplerState;
+#[cfg(feature = "fft_resampler")]
+pub use crate::synchro::{FftFilterParameters, FftFixedIn, FftFixedInOut, FftFixedOut};
+pub use crate::windows::{calculate_cutoff, calculate_kaiser_beta, WindowFunction};
 
 /// A resampler that is used to resample a chunk of audio to a new sample rate.
 /// For asynchronous resamplers, the rate can be adjusted as required.
@@ -72,9 +100,9 @@ where
     /// that allocates the output buffer with each call. For realtime applications, use
     /// [process_into_buffer](Resampler::process_into_buffer) with a buffer allocated by
     /// [output_buffer_allocate](Resampler::output_buffer_allocate) instead of this function.
-    fn process<V: AsRef<[T]>>(
+    fn process<Vin: AudioInput<T> + ?Sized>(
         &mut self,
-        wave_in: &[V],
+        wave_in: &Vin,
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<Vec<Vec<T>>> {
         let frames = self.output_frames_next();
@@ -101,15 +129,18 @@ where
     /// memory from the heap can cause glitches. If this is not a problem, you may use
     /// the [process](Resampler::process) method instead.
     ///
-    /// The input and output buffers are used in a non-interleaved format.
-    /// The input is a slice, where each element of the slice is itself referenceable
-    /// as a slice ([AsRef<\[T\]>](AsRef)) which contains the samples for a single channel.
-    /// Because `[Vec<T>]` implements [`AsRef<\[T\]>`](AsRef), the input may be [`Vec<Vec<T>>`](Vec).
+    /// The input is read through the [AudioInput] trait, and the output is written
+    /// through the [AudioOutput] trait.
+    /// These are implemented for slices, vectors and arrays of channels,
+    /// where each channel is referenceable as a slice ([AsRef<\[T\]>](AsRef))
+    /// which contains the samples for a single channel.
+    /// This means that the input and output may be for example [`Vec<Vec<T>>`](Vec) or `[&[T]]`.
+    /// Data stored in other layouts can be used via the [InterleavedSlice], [SequentialSlice]
+    /// and [StridedSlice] wrappers, or by implementing the traits for a custom type.
     ///
-    /// The output data is a slice, where each element of the slice is a `[T]` which contains
-    /// the samples for a single channel. If the output channel slices do not have sufficient
-    /// capacity for all output samples, the function will return an error with the expected
-    /// size. You could allocate the required output buffer with
+    /// If the output channels do not have sufficient capacity for all output samples,
+    /// the function will return an error with the expected size.
+    /// You could allocate the required output buffer with
     /// [output_buffer_allocate](Resampler::output_buffer_allocate) before calling this function
     /// and reuse the same buffer for each call.
     ///
@@ -124,12 +155,15 @@ where
     /// Both input and output are allowed to be longer than required.
     /// The number of input samples consumed and the number output samples written
     /// per channel is returned in a tuple, `(input_frames, output_frames)`.
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    fn process_into_buffer<Vin, Vout>(
         &mut self,
-        wave_in: &[Vin],
-        wave_out: &mut [Vout],
+        wave_in: &Vin,
+        wave_out: &mut Vout,
         active_channels_mask: Option<&[bool]>,
-    ) -> ResampleResult<(usize, usize)>;
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized;
 
     /// This is a convenience method for processing the last frames at the end of a stream.
     /// Use this when there are fewer frames remaining than what the resampler requires as input.
@@ -137,42 +171,85 @@ where
     /// to make it the right input length, and then calling [process_into_buffer](Resampler::process_into_buffer).
     /// This method can also be called without any input frames, by providing `None` as input buffer.
     /// This can be utilized to push any remaining delayed frames out from the internal buffers.
-    /// Note that this method allocates space for a temporary input buffer.
-    /// Real-time applications should instead call `process_into_buffer` with a zero-padded pre-allocated input buffer.
-    fn process_partial_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    /// The missing input frames are read as zeros, which means that this method does not allocate
+    /// and can be used in real-time applications.
+    /// Channels missing in the input are also read as zeros.
+    fn process_partial_into_buffer<Vin, Vout>(
         &mut self,
-        wave_in: Option<&[Vin]>,
-        wave_out: &mut [Vout],
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let channels = self.nbr_channels();
+        self.process_into_buffer(
+            &ZeroPadded::new(wave_in, channels),
+            wave_out,
+            active_channels_mask,
+        )
+    }
+
+    /// Resample a buffer of interleaved audio to a pre-allocated interleaved output buffer.
+    ///
+    /// This works like [process_into_buffer](Resampler::process_into_buffer),
+    /// but the input and output are given as slices containing interleaved samples,
+    /// `[L0, R0, L1, R1, ...]` for a stereo stream.
+    /// The samples are read directly from, and written directly to, the given slices,
+    /// without using any temporary buffers.
+    /// This is a convenience method that wraps the slices in [InterleavedSlice]
+    /// and calls [process_into_buffer](Resampler::process_into_buffer).
+    ///
+    /// The number of frames in a buffer is given by its length divided by the number of channels.
+    /// Samples belonging to channels marked as inactive by the `active_channels_mask`
+    /// are ignored in the input, and left unchanged in the output.
+    ///
+    /// Before processing, it checks that the input and output buffers hold enough frames.
+    /// If not, a [ResampleError] is returned.
+    /// The number of input frames consumed and the number output frames written
+    /// is returned in a tuple, `(input_frames, output_frames)`.
+    fn process_interleaved_into_buffer(
+        &mut self,
+        wave_in: &[T],
+        wave_out: &mut [T],
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<(usize, usize)> {
-        let frames = self.input_frames_next();
-        let mut wave_in_padded = Vec::with_capacity(self.nbr_channels());
-        for _ in 0..self.nbr_channels() {
-            wave_in_padded.push(vec![T::zero(); frames]);
-        }
-        if let Some(input) = wave_in {
-            for (ch_input, ch_padded) in input.iter().zip(wave_in_padded.iter_mut()) {
-                let mut frames_in = ch_input.as_ref().len();
-                if frames_in > frames {
-                    frames_in = frames;
-                }
-                if frames_in > 0 {
-                    ch_padded[..frames_in].copy_from_slice(&ch_input.as_ref()[..frames_in]);
-                } else {
-                    ch_padded.clear();
-                }
-            }
-        }
-        self.process_into_buffer(&wave_in_padded, wave_out, active_channels_mask)
+        let channels = self.nbr_channels();
+        self.process_into_buffer(
+            &InterleavedSlice::new(wave_in, channels),
+            &mut InterleavedSlice::new(wave_out, channels),
+            active_channels_mask,
+        )
+    }
+
+    /// This is a convenience method for processing the last frames of an interleaved stream.
+    /// It works like [process_partial_into_buffer](Resampler::process_partial_into_buffer),
+    /// but for interleaved input and output buffers,
+    /// see [process_interleaved_into_buffer](Resampler::process_interleaved_into_buffer).
+    /// The missing input frames are read as zeros, which means that this method does not allocate.
+    fn process_partial_interleaved_into_buffer(
+        &mut self,
+        wave_in: Option<&[T]>,
+        wave_out: &mut [T],
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)> {
+        let channels = self.nbr_channels();
+        let wave_in = wave_in.map(|wave_in| InterleavedSlice::new(wave_in, channels));
+        self.process_into_buffer(
+            &ZeroPadded::new(wave_in.as_ref(), channels),
+            &mut InterleavedSlice::new(wave_out, channels),
+            active_channels_mask,
+        )
     }
 
     /// This is a convenience method for processing the last frames at the end of a stream.
     /// It is similar to [process_partial_into_buffer](Resampler::process_partial_into_buffer)
     /// but allocates the output buffer with each call.
-    /// Note that this method allocates space for both input and output.
-    fn process_partial<V: AsRef<[T]>>(
+    fn process_partial<Vin: AudioInput<T> + ?Sized>(
         &mut self,
-        wave_in: Option<&[V]>,
+        wave_in: Option<&Vin>,
         active_channels_mask: Option<&[bool]>,
     ) -> ResampleResult<Vec<Vec<T>>> {
         let frames = self.output_frames_next();
@@ -194,6 +271,65 @@ where
         Ok(wave_out)
     }
 
+    /// Flush the resampler at the end of a stream,
+    /// and write the remaining output frames to a pre-allocated output buffer.
+    ///
+    /// The last frames of the stream, if any, are given as input.
+    /// These must be fewer than what the resampler needs for a normal call to
+    /// [process_into_buffer](Resampler::process_into_buffer),
+    /// any frames beyond [input_frames_next](Resampler::input_frames_next) are ignored.
+    /// The input is processed as by [process_partial_into_buffer](Resampler::process_partial_into_buffer),
+    /// and then the frames that are delayed in the internal buffers are pushed out of the resampler.
+    ///
+    /// Exactly the number of output frames still owed for the stream are written,
+    /// without any extra zeros at the end.
+    /// After discarding the first [output_delay](Resampler::output_delay) output frames,
+    /// the total number of output frames for the stream is then equal to the number of input frames
+    /// multiplied by the resampling ratio, rounded to the nearest integer.
+    /// The output buffer must have room for at least
+    /// [output_frames_remaining](Resampler::output_frames_remaining) plus
+    /// [output_frames_max](Resampler::output_frames_max) frames,
+    /// otherwise a [ResampleError] is returned.
+    /// The number of frames written is returned.
+    ///
+    /// This method does not allocate.
+    /// After flushing, the resampler must be [reset](Resampler::reset) before it is used for a new stream.
+    fn flush_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized;
+
+    /// This is a convenience method for flushing the resampler at the end of a stream.
+    /// It works like [flush_into_buffer](Resampler::flush_into_buffer),
+    /// but allocates and returns the output buffer.
+    fn flush<Vin: AudioInput<T> + ?Sized>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<Vec<Vec<T>>> {
+        let frames = self.output_frames_remaining() + self.output_frames_max();
+        let channels = self.nbr_channels();
+        let mut wave_out = Vec::with_capacity(channels);
+        for chan in 0..channels {
+            let chan_out = if active_channels_mask.map(|mask| mask[chan]).unwrap_or(true) {
+                vec![T::zero(); frames]
+            } else {
+                vec![]
+            };
+            wave_out.push(chan_out);
+        }
+        let out_len = self.flush_into_buffer(wave_in, &mut wave_out, active_channels_mask)?;
+        for chan_out in wave_out.iter_mut() {
+            chan_out.truncate(out_len);
+        }
+        Ok(wave_out)
+    }
+
     /// Convenience method for allocating an input buffer suitable for use with
     /// [process_into_buffer](Resampler::process_into_buffer). The buffer's capacity
     /// is big enough to prevent allocating additional heap memory before any call to
@@ -247,6 +383,15 @@ where
     /// Get the delay for the resampler, reported as a number of output frames.
     fn output_delay(&self) -> usize;
 
+    /// Get the number of output frames that remain to be output for the input frames processed so far,
+    /// including the frames delayed in the internal buffers.
+    /// This is the number of frames written by [flush_into_buffer](Resampler::flush_into_buffer)
+    /// when called without input.
+    /// The input frames are counted from when the resampler was created or [reset](Resampler::reset),
+    /// and the zeros used for padding by [process_partial_into_buffer](Resampler::process_partial_into_buffer)
+    /// are not counted.
+    fn output_frames_remaining(&self) -> usize;
+
     /// Update the resample ratio.
     ///
     /// For asynchronous resamplers, the ratio must be within
@@ -274,6 +419,15 @@ where
     /// For synchronous resamplers, this will always return [ResampleError::SyncNotAdjustable].
     fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> ResampleResult<()>;
 
+    /// Get the maximum relative ratio, that was provided to the constructor.
+    /// The relative ratio can be set to values within `1 / maximum` to `maximum`
+    /// with [set_resample_ratio_relative](Resampler::set_resample_ratio_relative).
+    ///
+    /// Synchronous resamplers can't change the ratio, and return 1.0.
+    fn max_resample_ratio_relative(&self) -> f64 {
+        1.0
+    }
+
     /// Reset the resampler state and clear all internal buffers.
     fn reset(&mut self);
 
@@ -300,8 +454,8 @@ use crate as rubato;
 /// which is needed to make the trait into an object.
 ///
 /// One wrapper trait, [VecResampler], is included per default.
-/// It differs from [Resampler] by fixing the generic types
-/// `&[AsRef<[T]>]` and `&mut [AsMut<[T]>]` to `&[Vec<T>]` and `&mut [Vec<T>]`.
+/// It differs from [Resampler] by fixing the
+/// generic input and output buffer types to `&[Vec<T>]` and `&mut [Vec<T>]`.
 /// This allows a [VecResampler] to be made into a trait object like this:
 /// ```
 /// # use rubato::{FastFixedIn, VecResampler, PolynomialDegree};
@@ -339,6 +493,22 @@ macro_rules! implement_resampler {
                 active_channels_mask: Option<&[bool]>,
             ) -> rubato::ResampleResult<(usize, usize)>;
 
+            /// Refer to [Resampler::process_interleaved_into_buffer].
+            fn process_interleaved_into_buffer(
+                &mut self,
+                wave_in: &[T],
+                wave_out: &mut [T],
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<(usize, usize)>;
+
+            /// Refer to [Resampler::process_partial_interleaved_into_buffer].
+            fn process_partial_interleaved_into_buffer(
+                &mut self,
+                wave_in: Option<&[T]>,
+                wave_out: &mut [T],
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<(usize, usize)>;
+
             /// Refer to [Resampler::process_partial].
             fn process_partial(
                 &mut self,
@@ -346,6 +516,21 @@ macro_rules! implement_resampler {
                 active_channels_mask: Option<&[bool]>,
             ) -> rubato::ResampleResult<Vec<Vec<T>>>;
 
+            /// Refer to [Resampler::flush_into_buffer].
+            fn flush_into_buffer(
+                &mut self,
+                wave_in: Option<$in_type>,
+                wave_out: $out_type,
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<usize>;
+
+            /// Refer to [Resampler::flush].
+            fn flush(
+                &mut self,
+                wave_in: Option<$in_type>,
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<Vec<Vec<T>>>;
+
             /// Refer to [Resampler::input_buffer_allocate].
             fn input_buffer_allocate(&self, filled: bool) -> Vec<Vec<T>>;
 
@@ -370,11 +555,17 @@ macro_rules! implement_resampler {
             /// Refer to [Resampler::output_delay].
             fn output_delay(&self) -> usize;
 
+            /// Refer to [Resampler::output_frames_remaining].
+            fn output_frames_remaining(&self) -> usize;
+
             /// Refer to [Resampler::set_resample_ratio].
             fn set_resample_ratio(&mut self, new_ratio: f64, ramp: bool) -> rubato::ResampleResult<()>;
 
             /// Refer to [Resampler::set_resample_ratio_relative].
             fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> rubato::ResampleResult<()>;
+
+            /// Refer to [Resampler::max_resample_ratio_relative].
+            fn max_resample_ratio_relative(&self) -> f64;
         }
 
         impl<T, U> $trait_name<T> for U
@@ -407,7 +598,30 @@ macro_rules! implement_resampler {
             ) -> rubato::ResampleResult<(usize, usize)> {
                 rubato::Resampler::process_partial_into_buffer(
                     self,
-                    wave_in.map(AsRef::as_ref),
+                    wave_in,
+                    wave_out,
+                    active_channels_mask,
+                )
+            }
+
+            fn process_interleaved_into_buffer(
+                &mut self,
+                wave_in: &[T],
+                wave_out: &mut [T],
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<(usize, usize)> {
+                rubato::Resampler::process_interleaved_into_buffer(self, wave_in, wave_out, active_channels_mask)
+            }
+
+            fn process_partial_interleaved_into_buffer(
+                &mut self,
+                wave_in: Option<&[T]>,
+                wave_out: &mut [T],
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<(usize, usize)> {
+                rubato::Resampler::process_partial_interleaved_into_buffer(
+                    self,
+                    wave_in,
                     wave_out,
                     active_channels_mask,
                 )
@@ -421,6 +635,23 @@ macro_rules! implement_resampler {
                 rubato::Resampler::process_partial(self, wave_in, active_channels_mask)
             }
 
+            fn flush_into_buffer(
+                &mut self,
+                wave_in: Option<$in_type>,
+                wave_out: $out_type,
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<usize> {
+                rubato::Resampler::flush_into_buffer(self, wave_in, wave_out, active_channels_mask)
+            }
+
+            fn flush(
+                &mut self,
+                wave_in: Option<$in_type>,
+                active_channels_mask: Option<&[bool]>,
+            ) -> rubato::ResampleResult<Vec<Vec<T>>> {
+                rubato::Resampler::flush(self, wave_in, active_channels_mask)
+            }
+
             fn output_buffer_allocate(&self, filled: bool) -> Vec<Vec<T>> {
                 rubato::Resampler::output_buffer_allocate(self, filled)
             }
@@ -441,6 +672,10 @@ macro_rules! implement_resampler {
                 rubato::Resampler::output_delay(self)
             }
 
+            fn output_frames_remaining(&self) -> usize {
+                rubato::Resampler::output_frames_remaining(self)
+            }
+
             fn nbr_channels(&self) -> usize {
                 rubato::Resampler::nbr_channels(self)
             }
@@ -460,6 +695,10 @@ macro_rules! implement_resampler {
             fn set_resample_ratio_relative(&mut self, rel_ratio: f64, ramp: bool) -> rubato::ResampleResult<()> {
                 rubato::Resampler::set_resample_ratio_relative(self, rel_ratio, ramp)
             }
+
+            fn max_resample_ratio_relative(&self) -> f64 {
+                rubato::Resampler::max_resample_ratio_relative(self)
+            }
         }
     }
 }
@@ -471,28 +710,184 @@ fn update_mask_from_buffers(mask: &mut [bool]) {
     mask.iter_mut().for_each(|v| *v = true);
 }
 
-pub(crate) fn validate_buffers<T, Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
-    wave_in: &[Vin],
-    wave_out: &mut [Vout],
+/// Get the number of frames of a partial input, limited to the given maximum.
+/// The longest channel determines the number of frames.
+fn partial_input_frames<T, Vin>(wave_in: Option<&Vin>, max_frames: usize) -> usize
+where
+    T: Sample,
+    Vin: AudioInput<T> + ?Sized,
+{
+    wave_in
+        .map(|input| {
+            (0..input.nbr_channels())
+                .map(|chan| input.nbr_frames(chan))
+                .max()
+                .unwrap_or(0)
+        })
+        .unwrap_or(0)
+        .min(max_frames)
+}
+
+/// Flush a resampler, see [Resampler::flush_into_buffer].
+/// The `counter` function gives the frame counter of the resampler.
+fn flush_resampler<T, R, Vin, Vout>(
+    resampler: &mut R,
+    counter: fn(&R) -> FrameCounter,
+    wave_in: Option<&Vin>,
+    wave_out: &mut Vout,
+    active_channels_mask: Option<&[bool]>,
+) -> ResampleResult<usize>
+where
+    T: Sample,
+    R: Resampler<T>,
+    Vin: AudioInput<T> + ?Sized,
+    Vout: AudioOutput<T> + ?Sized,
+{
+    let channels = resampler.nbr_channels();
+    if wave_out.nbr_channels() != channels {
+        return Err(ResampleError::WrongNumberOfOutputChannels {
+            expected: channels,
+            actual: wave_out.nbr_channels(),
+        });
+    }
+    if let Some(mask) = active_channels_mask {
+        if mask.len() != channels {
+            return Err(ResampleError::WrongNumberOfMaskChannels {
+                expected: channels,
+                actual: mask.len(),
+            });
+        }
+    }
+    let needed = resampler.output_frames_remaining() + resampler.output_frames_max();
+    let mut capacity = usize::MAX;
+    for chan in 0..channels {
+        if active_channels_mask.map(|mask| mask[chan]).unwrap_or(true) {
+            let actual = wave_out.nbr_frames(chan);
+            if actual < needed {
+                return Err(ResampleError::InsufficientOutputBufferSize {
+                    channel: chan,
+                    expected: needed,
+                    actual,
+                });
+            }
+            capacity = capacity.min(actual);
+        }
+    }
+
+    // Process the last frames of the stream.
+    // This determines the total number of frames to output.
+    let produced_before = counter(resampler).produced;
+    let (_, mut written) = resampler.process_partial_into_buffer(
+        wave_in,
+        &mut OutputWindow::new(&mut *wave_out, 0, capacity),
+        active_channels_mask,
+    )?;
+    let total = counter(resampler)
+        .total(resampler.output_delay())
+        .saturating_sub(produced_before);
+
+    // Push out the delayed frames.
+    // Some calls may not give any output, for example when the chunks are smaller than
+    // the FFT of a synchronous resampler, but the resampler must not stop making progress.
+    // Padding that corresponds to the remaining frames, plus twice the delay and
+    // the largest chunk, is more than enough for all frames to come out.
+    let padding_before = counter(resampler).padding;
+    let padding_max = (total.saturating_sub(written)
+        + 2 * (resampler.output_delay() + resampler.output_frames_max()))
+        as f64;
+    while written < total {
+        let (frames_in, frames_out) = resampler.process_partial_into_buffer(
+            None::<&[Vec<T>]>,
+            &mut OutputWindow::new(&mut *wave_out, written, total),
+            active_channels_mask,
+        )?;
+        written += frames_out;
+        let padding = counter(resampler).padding - padding_before;
+        if written < total && ((frames_in == 0 && frames_out == 0) || padding > padding_max) {
+            return Err(ResampleError::FlushStalled {
+                expected: total,
+                actual: written,
+            });
+        }
+    }
+    Ok(total)
+}
+
+/// Keeps count of the frames processed since a resampler was created or reset,
+/// for calculating the number of frames that remain to be output at the end of a stream.
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameCounter {
+    /// The number of output frames corresponding to the input frames processed so far.
+    expected: f64,
+    /// The number of output frames produced so far.
+    produced: usize,
+    /// The number of output frames corresponding to the padding processed so far,
+    /// used for checking that flushing makes progress.
+    padding: f64,
+}
+
+impl FrameCounter {
+    /// Count a processed chunk, with the average resampling ratio used for the chunk.
+    fn add(&mut self, frames_in: usize, frames_out: usize, ratio: f64) {
+        self.expected += frames_in as f64 * ratio;
+        self.produced += frames_out;
+    }
+
+    /// Remove input frames that were only padding, and not part of the stream.
+    fn remove_padding(&mut self, frames: usize, ratio: f64) {
+        self.expected -= frames as f64 * ratio;
+        self.padding += frames as f64 * ratio;
+    }
+
+    /// Get the total number of frames to output for the stream, given the delay of the resampler.
+    fn total(&self, delay: usize) -> usize {
+        self.expected.round().max(0.0) as usize + delay
+    }
+
+    /// Get the number of frames that remain to be output, given the delay of the resampler.
+    fn remaining(&self, delay: usize) -> usize {
+        self.total(delay).saturating_sub(self.produced)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn validate_sample_rates(input: usize, output: usize) -> Result<(), ResamplerConstructionError> {
+    if input == 0 || output == 0 {
+        return Err(ResamplerConstructionError::InvalidSampleRate { input, output });
+    }
+    Ok(())
+}
+
+pub(crate) fn validate_buffers<T, Vin, Vout>(
+    wave_in: &Vin,
+    wave_out: &Vout,
     mask: &[bool],
     channels: usize,
     min_input_len: usize,
     min_output_len: usize,
-) -> ResampleResult<()> {
-    if wave_in.len() != channels {
+) -> ResampleResult<()>
+where
+    T: Sample,
+    Vin: AudioInput<T> + ?Sized,
+    Vout: AudioOutput<T> + ?Sized,
+{
+    if wave_in.nbr_channels() != channels {
         return Err(ResampleError::WrongNumberOfInputChannels {
             expected: channels,
-            actual: wave_in.len(),
+            actual: wave_in.nbr_channels(),
         });
     }
     if mask.len() != channels {
         return Err(ResampleError::WrongNumberOfMaskChannels {
             expected: channels,
-            actual: wave_in.len(),
+            actual: mask.len(),
         });
     }
-    for (chan, wave_in) in wave_in.iter().enumerate().filter(|(chan, _)| mask[*chan]) {
-        let actual_len = wave_in.as_ref().len();
+    for chan in (0..channels).filter(|chan| mask[*chan]) {
+        let actual_len = wave_in.nbr_frames(chan);
         if actual_len < min_input_len {
             return Err(ResampleError::InsufficientInputBufferSize {
                 channel: chan,
@@ -501,18 +896,14 @@ pub(crate) fn validate_buffers<T, Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
             });
         }
     }
-    if wave_out.len() != channels {
+    if wave_out.nbr_channels() != channels {
         return Err(ResampleError::WrongNumberOfOutputChannels {
             expected: channels,
-            actual: wave_out.len(),
+            actual: wave_out.nbr_channels(),
         });
     }
-    for (chan, wave_out) in wave_out
-        .iter_mut()
-        .enumerate()
-        .filter(|(chan, _)| mask[*chan])
-    {
-        let actual_len = wave_out.as_mut().len();
+    for chan in (0..channels).filter(|chan| mask[*chan]) {
+        let actual_len = wave_out.nbr_frames(chan);
         if actual_len < min_output_len {
             return Err(ResampleError::InsufficientOutputBufferSize {
                 channel: chan,
@@ -550,39 +941,37 @@ pub fn resize_buffer<T: Sample>(buffer: &mut [Vec<T>], frames: usize) {
 /// Convenience method for getting the current length of a buffer in frames.
 /// Checks the [length](Vec::len) of the vector for each channel and returns the smallest.
 pub fn buffer_length<T: Sample>(buffer: &[Vec<T>]) -> usize {
-    return buffer.iter().map(|v| v.len()).min().unwrap_or_default();
+    buffer.iter().map(|v| v.len()).min().unwrap_or_default()
 }
 
 /// Convenience method for getting the current allocated capacity of a buffer in frames.
 /// Checks the [capacity](Vec::capacity) of the vector for each channel and returns the smallest.
 pub fn buffer_capacity<T: Sample>(buffer: &[Vec<T>]) -> usize {
-    return buffer
+    buffer
         .iter()
         .map(|v| v.capacity())
         .min()
-        .unwrap_or_default();
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::Resampler;
     use crate::{buffer_capacity, buffer_length, make_buffer, resize_buffer, VecResampler};
-    use crate::{FastFixedIn, PolynomialDegree, SincFixedIn, SincFixedOut};
+    use crate::{FastFixedIn, FastFixedOut, PolynomialDegree, PolyphaseFixedIn};
     #[cfg(feature = "fft_resampler")]
     use crate::{FftFixedIn, FftFixedInOut, FftFixedOut};
+    use crate::{SincFixedIn, SincFixedOut};
+    use crate::{SincInterpolationParameters, SincInterpolationType, SincPhase, WindowFunction};
+    use rand::Rng;
     use test_log::test;
 
     // This tests that a VecResampler can be boxed.
     #[test]
     fn boxed_resampler() {
         let mut boxed: Box<dyn VecResampler<f64>> = Box::new(
-            FastFixedIn::<f64>::new(
-                88200 as f64 / 44100 as f64,
-                1.1,
-                PolynomialDegree::Cubic,
-                1024,
-                2,
-            )
-            .unwrap(),
+            FastFixedIn::<f64>::new(88200.0 / 44100.0, 1.1, PolynomialDegree::Cubic, 1024, 2)
+                .unwrap(),
         );
         let _ = process_with_boxed(&mut boxed);
         let result = process_with_boxed(&mut boxed);
@@ -616,6 +1005,68 @@ pub mod tests {
         impl_send::<f64>();
     }
 
+    fn impl_sync<T: Send + Sync>() {
+        fn is_sync<T: Sync>() {}
+        is_sync::<SincFixedOut<T>>();
+        is_sync::<SincFixedIn<T>>();
+        #[cfg(feature = "fft_resampler")]
+        {
+            is_sync::<FftFixedOut<T>>();
+            is_sync::<FftFixedIn<T>>();
+            is_sync::<FftFixedInOut<T>>();
+        }
+    }
+
+    // This tests that all resamplers are Sync.
+    #[test]
+    fn test_impl_sync() {
+        impl_sync::<f32>();
+        impl_sync::<f64>();
+    }
+
+    // Process a chunk, clone the resampler, and check that the original
+    // and the clone give identical output for the following chunks.
+    fn check_clone<R: Resampler<f64> + Clone>(mut resampler: R) {
+        let mut rng = rand::thread_rng();
+        let mut next_chunk = |frames: usize| -> Vec<Vec<f64>> {
+            vec![(0..frames).map(|_| rng.gen_range(-1.0..1.0)).collect(); 2]
+        };
+        let waves = next_chunk(resampler.input_frames_next());
+        resampler.process(&waves, None).unwrap();
+        let mut cloned = resampler.clone();
+        for _ in 0..3 {
+            assert_eq!(resampler.input_frames_next(), cloned.input_frames_next());
+            let waves = next_chunk(resampler.input_frames_next());
+            let expected = resampler.process(&waves, None).unwrap();
+            let out = cloned.process(&waves, None).unwrap();
+            assert_eq!(out, expected);
+        }
+    }
+
+    // This tests that all resamplers can be cloned while running.
+    #[test]
+    fn clone_resamplers() {
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+            phase: SincPhase::Linear,
+        };
+        check_clone(SincFixedIn::<f64>::new(1.2, 1.1, params, 1024, 2).unwrap());
+        let params = SincInterpolationParameters::draft();
+        check_clone(SincFixedOut::<f64>::new(1.2, 1.1, params, 1024, 2).unwrap());
+        check_clone(FastFixedIn::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap());
+        check_clone(FastFixedOut::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap());
+        #[cfg(feature = "fft_resampler")]
+        {
+            check_clone(FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap());
+            check_clone(FftFixedOut::<f64>::new(44100, 48000, 1024, 2, 2).unwrap());
+            check_clone(FftFixedInOut::<f64>::new(44100, 48000, 1024, 2).unwrap());
+        }
+    }
+
     #[macro_export]
     macro_rules! check_output {
         ($resampler:ident) => {
@@ -646,7 +1097,7 @@ pub mod tests {
                     for ch in 0..2 {
                         waves[ch][m] = val;
                     }
-                    val = val + 0.1;
+                    val += 0.1;
                 }
                 let out = $resampler.process(&waves, None).unwrap();
                 let frames_out = out[0].len();
@@ -706,6 +1157,377 @@ pub mod tests {
         };
     }
 
+    // Process a chunk, save the state and restore it in a second resampler.
+    // Then check that both resamplers give identical output for the following chunks.
+    #[macro_export]
+    macro_rules! check_state {
+        ($resampler:ident, $restored:ident) => {
+            let mut rng = rand::thread_rng();
+            let mut next_chunk = |frames: usize| -> Vec<Vec<f64>> {
+                vec![(0..frames).map(|_| rng.gen_range(-1.0..1.0)).collect(); 2]
+            };
+            let waves = next_chunk($resampler.input_frames_next());
+            $resampler.process(&waves, None).unwrap();
+            let state = $resampler.state();
+            $restored.set_state(&state).unwrap();
+            assert_eq!($restored.state(), state);
+            for _ in 0..3 {
+                assert_eq!(
+                    $resampler.input_frames_next(),
+                    $restored.input_frames_next()
+                );
+                let waves = next_chunk($resampler.input_frames_next());
+                let expected = $resampler.process(&waves, None).unwrap();
+                let out = $restored.process(&waves, None).unwrap();
+                assert_eq!(out, expected);
+            }
+        };
+    }
+
+    // Process chunks while ramping the ratio between the limits, and check that
+    // every state snapshot can be restored. Then check that snapshots with
+    // a corrupted index or expected number of output frames are rejected.
+    #[macro_export]
+    macro_rules! check_corrupt_state {
+        ($resampler:ident, $restored:ident, $ratio:expr) => {
+            for ratio in [$ratio, 0.5 * $ratio, 2.0 * $ratio, $ratio] {
+                $resampler.set_resample_ratio(ratio, true).unwrap();
+                let waves = vec![vec![0.5; $resampler.input_frames_next()]; 2];
+                $resampler.process(&waves, None).unwrap();
+                $restored.set_state(&$resampler.state()).unwrap();
+            }
+            let state = $resampler.state();
+            for last_index in [f64::NAN, f64::INFINITY, -1.0e9, 1.0e9] {
+                let corrupt = $crate::AsyncResamplerState {
+                    last_index,
+                    ..state.clone()
+                };
+                assert!(matches!(
+                    $restored.set_state(&corrupt),
+                    Err($crate::ResampleError::IncompatibleState)
+                ));
+            }
+            let corrupt = $crate::AsyncResamplerState {
+                output_frames_expected: f64::NAN,
+                ..state.clone()
+            };
+            assert!(matches!(
+                $restored.set_state(&corrupt),
+                Err($crate::ResampleError::IncompatibleState)
+            ));
+            $restored.set_state(&state).unwrap();
+        };
+    }
+
+    fn make_boxed_resamplers() -> Vec<Box<dyn VecResampler<f64>>> {
+        let params = || SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+            phase: SincPhase::Linear,
+        };
+        let resamplers: Vec<Box<dyn VecResampler<f64>>> = vec![
+            Box::new(SincFixedIn::<f64>::new(1.2, 1.0, params(), 256, 2).unwrap()),
+            Box::new(
+                SincFixedOut::<f64>::new(
+                    1.2,
+                    1.0,
+                    SincInterpolationParameters {
+                        interpolation: SincInterpolationType::Linear,
+                        ..params()
+                    },
+                    256,
+                    2,
+                )
+                .unwrap(),
+            ),
+            Box::new(FastFixedIn::<f64>::new(0.8, 1.0, PolynomialDegree::Septic, 256, 2).unwrap()),
+            Box::new(FastFixedOut::<f64>::new(0.8, 1.0, PolynomialDegree::Cubic, 256, 2).unwrap()),
+            Box::new(PolyphaseFixedIn::<f64>::new(44100, 48000, params(), 256, 2).unwrap()),
+        ];
+        #[cfg(feature = "fft_resampler")]
+        let resamplers = {
+            let mut resamplers = resamplers;
+            resamplers.push(Box::new(
+                FftFixedIn::<f64>::new(44100, 48000, 256, 2, 2).unwrap(),
+            ));
+            resamplers.push(Box::new(
+                FftFixedOut::<f64>::new(44100, 48000, 256, 2, 2).unwrap(),
+            ));
+            resamplers.push(Box::new(
+                FftFixedInOut::<f64>::new(44100, 48000, 256, 2).unwrap(),
+            ));
+            resamplers
+        };
+        resamplers
+    }
+
+    fn interleave(wave: &[Vec<f64>], frames: usize) -> Vec<f64> {
+        (0..frames)
+            .flat_map(|frame| wave.iter().map(move |chan| chan[frame]))
+            .collect()
+    }
+
+    // This tests that all resamplers give the same result for interleaved and non-interleaved data.
+    #[test]
+    fn interleaved_matches_non_interleaved() {
+        let mut rng = rand::thread_rng();
+        for (mut planar, mut interleaved) in make_boxed_resamplers()
+            .into_iter()
+            .zip(make_boxed_resamplers())
+        {
+            let mut wave_out = vec![0.0; 2 * interleaved.output_frames_max()];
+            for _ in 0..5 {
+                let frames = planar.input_frames_next();
+                let wave_in = vec![(0..frames).map(|_| rng.gen::<f64>()).collect::<Vec<_>>(); 2];
+                let expected = planar.process(&wave_in, None).unwrap();
+                let (frames_in, frames_out) = interleaved
+                    .process_interleaved_into_buffer(
+                        &interleave(&wave_in, frames),
+                        &mut wave_out,
+                        None,
+                    )
+                    .unwrap();
+                assert_eq!(frames_in, frames);
+                assert_eq!(frames_out, expected[0].len());
+                assert_eq!(
+                    wave_out[..2 * frames_out],
+                    interleave(&expected, frames_out)[..]
+                );
+            }
+            let wave_in = vec![vec![0.5; 10]; 2];
+            let expected = planar.process_partial(Some(&wave_in), None).unwrap();
+            let (_, frames_out) = interleaved
+                .process_partial_interleaved_into_buffer(
+                    Some(&interleave(&wave_in, 10)),
+                    &mut wave_out,
+                    None,
+                )
+                .unwrap();
+            assert_eq!(
+                wave_out[..2 * frames_out],
+                interleave(&expected, frames_out)[..]
+            );
+        }
+    }
+
+    // Check that partial processing gives the same result as processing a zero padded input.
+    fn check_partial<R: Resampler<f64> + Clone>(resampler: R) {
+        let frames = resampler.input_frames_next();
+        let mut output = resampler.output_buffer_allocate(true);
+        let mut expected_output = resampler.output_buffer_allocate(true);
+
+        let wave_in = vec![vec![0.5; frames / 2]; 2];
+        let mut padded = vec![vec![0.0; frames]; 2];
+        padded[0][..frames / 2].copy_from_slice(&wave_in[0]);
+        padded[1][..frames / 2].copy_from_slice(&wave_in[1]);
+        let mut partial = resampler.clone();
+        let mut reference = resampler.clone();
+        let result = partial
+            .process_partial_into_buffer(Some(&wave_in), &mut output, None)
+            .unwrap();
+        let expected = reference
+            .process_into_buffer(&padded, &mut expected_output, None)
+            .unwrap();
+        assert_eq!(result, expected);
+        assert_eq!(output, expected_output);
+
+        // A missing channel reads as zeros.
+        let mut partial = resampler.clone();
+        let mut reference = resampler.clone();
+        padded[1].iter_mut().for_each(|s| *s = 0.0);
+        partial
+            .process_partial_into_buffer(Some(&wave_in[..1]), &mut output, None)
+            .unwrap();
+        reference
+            .process_into_buffer(&padded, &mut expected_output, None)
+            .unwrap();
+        assert_eq!(output, expected_output);
+
+        // No input at all reads as zeros.
+        let mut partial = resampler.clone();
+        let mut reference = resampler;
+        partial
+            .process_partial_into_buffer(None::<&[Vec<f64>]>, &mut output, None)
+            .unwrap();
+        reference
+            .process_into_buffer(&vec![vec![0.0; frames]; 2], &mut expected_output, None)
+            .unwrap();
+        assert_eq!(output, expected_output);
+    }
+
+    #[test]
+    fn process_partial_into_buffer() {
+        check_partial(
+            SincFixedIn::<f64>::new(1.2, 1.1, SincInterpolationParameters::draft(), 1024, 2)
+                .unwrap(),
+        );
+        check_partial(
+            SincFixedOut::<f64>::new(1.2, 1.1, SincInterpolationParameters::draft(), 1024, 2)
+                .unwrap(),
+        );
+        check_partial(FastFixedIn::<f64>::new(0.8, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap());
+        check_partial(
+            FastFixedOut::<f64>::new(0.8, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap(),
+        );
+        #[cfg(feature = "fft_resampler")]
+        {
+            check_partial(FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap());
+            check_partial(FftFixedOut::<f64>::new(44100, 48000, 1024, 2, 2).unwrap());
+            check_partial(FftFixedInOut::<f64>::new(44100, 48000, 1024, 2).unwrap());
+        }
+    }
+
+    // Resample a stream of ones, flush, and check that the output has the expected length.
+    fn check_flush<R: Resampler<f64>>(mut resampler: R, ratio: f64, frames: usize) {
+        let mut output: Vec<Vec<f64>> = vec![Vec::new(); 2];
+        let mut remaining = frames;
+        while remaining >= resampler.input_frames_next() {
+            let wave_in = vec![vec![1.0; resampler.input_frames_next()]; 2];
+            let out = resampler.process(&wave_in, None).unwrap();
+            output[0].extend_from_slice(&out[0]);
+            output[1].extend_from_slice(&out[1]);
+            remaining -= wave_in[0].len();
+        }
+        let delay = resampler.output_delay();
+        let wave_in = vec![vec![1.0; remaining]; 2];
+        let flushed = resampler.flush(Some(&wave_in), None).unwrap();
+        assert_eq!(resampler.output_frames_remaining(), 0);
+        output[0].extend_from_slice(&flushed[0]);
+        output[1].extend_from_slice(&flushed[1]);
+        let expected_len = (frames as f64 * ratio).round() as usize;
+        assert_eq!(output[0].len(), delay + expected_len);
+        assert_eq!(output[1].len(), delay + expected_len);
+        let output = &output[0][delay..];
+        assert!(output[output.len() / 2] > 0.9);
+    }
+
+    // Make a test stream of a slow sine.
+    pub fn sine_stream(frames: usize) -> Vec<f64> {
+        (0..frames).map(|n| (n as f64 * 0.01).sin()).collect()
+    }
+
+    // Process a stream on two identical channels with a resampler, in its own chunk sizes,
+    // and flush it at the end. This is the reference output for the resampler wrappers.
+    pub fn reference_output<R: Resampler<f64>>(mut resampler: R, stream: &[f64]) -> Vec<f64> {
+        let mut output = Vec::new();
+        let mut pos = 0;
+        while stream.len() - pos >= resampler.input_frames_next() {
+            let chunk = &stream[pos..pos + resampler.input_frames_next()];
+            let out = resampler.process(&[chunk, chunk], None).unwrap();
+            output.extend_from_slice(&out[0]);
+            pos += chunk.len();
+        }
+        let out = resampler
+            .flush(Some(&[&stream[pos..], &stream[pos..]]), None)
+            .unwrap();
+        output.extend_from_slice(&out[0]);
+        output
+    }
+
+    // Resample two chunks, ramp the ratio during the last partial chunk and flush.
+    // Check that the output length follows the average ratio of the ramp.
+    fn check_flush_ramp<R: Resampler<f64>>(mut resampler: R, ratio: f64, new_ratio: f64) {
+        let mut output_len = 0;
+        let mut input_len = 0;
+        for _ in 0..2 {
+            let wave_in = vec![vec![1.0; resampler.input_frames_next()]; 2];
+            output_len += resampler.process(&wave_in, None).unwrap()[0].len();
+            input_len += wave_in[0].len();
+        }
+        resampler.set_resample_ratio(new_ratio, true).unwrap();
+        let remaining = resampler.input_frames_next() / 2;
+        let wave_in = vec![vec![1.0; remaining]; 2];
+        output_len += resampler.flush(Some(&wave_in), None).unwrap()[0].len();
+        let expected_len =
+            (input_len as f64 * ratio + remaining as f64 * 0.5 * (ratio + new_ratio)).round();
+        assert_eq!(output_len, expected_len as usize + resampler.output_delay());
+    }
+
+    #[test]
+    fn flush_ramped_ratio() {
+        let params = SincInterpolationParameters::draft();
+        check_flush_ramp(
+            SincFixedIn::<f64>::new(1.2, 1.5, params, 1024, 2).unwrap(),
+            1.2,
+            1.6,
+        );
+        let params = SincInterpolationParameters::draft();
+        check_flush_ramp(
+            SincFixedOut::<f64>::new(0.7, 1.5, params, 1024, 2).unwrap(),
+            0.7,
+            0.5,
+        );
+        check_flush_ramp(
+            FastFixedIn::<f64>::new(1.2, 1.5, PolynomialDegree::Cubic, 1024, 2).unwrap(),
+            1.2,
+            1.6,
+        );
+        check_flush_ramp(
+            FastFixedOut::<f64>::new(0.7, 1.5, PolynomialDegree::Cubic, 1024, 2).unwrap(),
+            0.7,
+            0.5,
+        );
+    }
+
+    #[test]
+    fn flush_resamplers() {
+        let params = SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.95,
+            interpolation: SincInterpolationType::Cubic,
+            oversampling_factor: 16,
+            window: WindowFunction::BlackmanHarris2,
+            phase: SincPhase::Linear,
+        };
+        check_flush(
+            SincFixedIn::<f64>::new(1.2, 1.1, params, 1024, 2).unwrap(),
+            1.2,
+            5000,
+        );
+        let params = SincInterpolationParameters::draft();
+        check_flush(
+            SincFixedOut::<f64>::new(0.7, 1.1, params, 1024, 2).unwrap(),
+            0.7,
+            5000,
+        );
+        check_flush(
+            FastFixedIn::<f64>::new(1.2, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap(),
+            1.2,
+            5000,
+        );
+        check_flush(
+            FastFixedOut::<f64>::new(0.7, 1.1, PolynomialDegree::Cubic, 1024, 2).unwrap(),
+            0.7,
+            5000,
+        );
+        #[cfg(feature = "fft_resampler")]
+        {
+            check_flush(
+                FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap(),
+                48000.0 / 44100.0,
+                5000,
+            );
+            check_flush(
+                FftFixedOut::<f64>::new(48000, 44100, 1024, 2, 2).unwrap(),
+                44100.0 / 48000.0,
+                5000,
+            );
+            check_flush(
+                FftFixedInOut::<f64>::new(44100, 48000, 1024, 2).unwrap(),
+                48000.0 / 44100.0,
+                5000,
+            );
+            // Chunks much smaller than the FFT, where most calls give no output.
+            let mut resampler =
+                FftFixedIn::<f64>::new_with_filter_length(44100, 48000, 1024, 1, 4096, 2).unwrap();
+            resampler.set_chunk_size(7).unwrap();
+            check_flush(resampler, 48000.0 / 44100.0, 5000);
+        }
+    }
+
     #[test]
     fn test_buffer_helpers() {
         let buf1 = vec![vec![0.0f64; 7], vec![0.0f64; 5], vec![0.0f64; 10]];
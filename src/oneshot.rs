@@ -0,0 +1,175 @@
+use crate::adapters::{AudioInput, InputWindow, OutputWindow};
+use crate::error::ResamplerConstructionError;
+use crate::{Resampler, Sample, SincFixedIn, SincInterpolationParameters};
+
+/// The chunk size used for resampling a whole clip.
+const CHUNK_SIZE: usize = 1024;
+
+/// The quality of the resampling done by [resample_all].
+///
+/// Each quality level corresponds to one of the presets of [SincInterpolationParameters],
+/// see these for the measured properties and the relative CPU cost.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResampleQuality {
+    /// Fast resampling with moderate quality, see [SincInterpolationParameters::draft].
+    Draft,
+    /// Good quality at a moderate CPU cost, see [SincInterpolationParameters::balanced].
+    Balanced,
+    /// High quality, see [SincInterpolationParameters::high].
+    High,
+    /// The highest quality, see [SincInterpolationParameters::mastering].
+    Mastering,
+}
+
+impl ResampleQuality {
+    /// Get the sinc interpolation parameters for this quality level.
+    pub fn sinc_parameters(&self) -> SincInterpolationParameters {
+        match self {
+            ResampleQuality::Draft => SincInterpolationParameters::draft(),
+            ResampleQuality::Balanced => SincInterpolationParameters::balanced(),
+            ResampleQuality::High => SincInterpolationParameters::high(),
+            ResampleQuality::Mastering => SincInterpolationParameters::mastering(),
+        }
+    }
+}
+
+/// Resample a whole audio clip from sample rate `fs_in` to `fs_out`.
+///
+/// This follows the procedure for [resampling a given audio clip](crate#resampling-a-given-audio-clip),
+/// using a [SincFixedIn] resampler with the parameters given by `quality`.
+/// The [output_delay](Resampler::output_delay) of the resampler is removed,
+/// so that the first output frame corresponds to the first input frame,
+/// to within half an output frame.
+/// The returned clip has one vector per channel, each with a length equal to
+/// the number of input frames multiplied by `fs_out / fs_in`, rounded to the nearest integer.
+/// If the sample rates are equal, the input is returned unchanged.
+///
+/// The number of input frames is given by the shortest channel,
+/// any frames beyond this are ignored.
+///
+/// This function allocates, and is intended for offline processing.
+/// Use a [Resampler] directly for processing streams, or for real-time use.
+pub fn resample_all<T, Vin>(
+    wave_in: &Vin,
+    fs_in: usize,
+    fs_out: usize,
+    quality: ResampleQuality,
+) -> Result<Vec<Vec<T>>, ResamplerConstructionError>
+where
+    T: Sample,
+    Vin: AudioInput<T> + ?Sized,
+{
+    if fs_in == 0 || fs_out == 0 {
+        return Err(ResamplerConstructionError::InvalidSampleRate {
+            input: fs_in,
+            output: fs_out,
+        });
+    }
+    let channels = wave_in.nbr_channels();
+    let frames = (0..channels)
+        .map(|chan| wave_in.nbr_frames(chan))
+        .min()
+        .unwrap_or(0);
+    if fs_in == fs_out {
+        let mut wave_out = vec![vec![T::zero(); frames]; channels];
+        for (chan, chan_out) in wave_out.iter_mut().enumerate() {
+            wave_in.copy_from_channel_to_slice(chan, 0, chan_out);
+        }
+        return Ok(wave_out);
+    }
+    if channels == 0 {
+        return Ok(Vec::new());
+    }
+
+    let ratio = fs_out as f64 / fs_in as f64;
+    let mut resampler =
+        SincFixedIn::<T>::new(ratio, 1.0, quality.sinc_parameters(), CHUNK_SIZE, channels)?;
+    let delay = resampler.output_delay();
+    let total = delay + (frames as f64 * ratio).round() as usize;
+    let mut wave_out = vec![vec![T::zero(); total]; channels];
+
+    // The buffers are always large enough, so processing can not fail.
+    let mut frames_read = 0;
+    let mut frames_written = 0;
+    while frames - frames_read >= resampler.input_frames_next() {
+        let (nbr_in, nbr_out) = resampler
+            .process_into_buffer(
+                &InputWindow::new(wave_in, frames_read, frames),
+                &mut OutputWindow::new(&mut wave_out, frames_written, total),
+                None,
+            )
+            .unwrap();
+        frames_read += nbr_in;
+        frames_written += nbr_out;
+    }
+    resampler
+        .flush_into_buffer(
+            Some(&InputWindow::new(wave_in, frames_read, frames)),
+            &mut OutputWindow::new(&mut wave_out, frames_written, total),
+            None,
+        )
+        .unwrap();
+
+    for chan_out in wave_out.iter_mut() {
+        chan_out.drain(..delay);
+    }
+    Ok(wave_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{resample_all, ResampleQuality, ResamplerConstructionError};
+    use std::f64::consts::PI;
+    use test_log::test;
+
+    fn sine(freq: f64, fs: f64, frames: usize) -> Vec<f64> {
+        (0..frames)
+            .map(|n| (2.0 * PI * freq * n as f64 / fs).sin())
+            .collect()
+    }
+
+    #[test]
+    fn resample_clip() {
+        let wave_in = vec![sine(100.0, 44100.0, 10000); 2];
+        let wave_out = resample_all(&wave_in, 44100, 48000, ResampleQuality::Balanced).unwrap();
+        assert_eq!(wave_out.len(), 2);
+        // 10000 * 48000 / 44100 = 10884.35
+        assert_eq!(wave_out[0].len(), 10884);
+        assert_eq!(wave_out[1].len(), 10884);
+        // The delay is removed, so the output is aligned with the input to within half a frame.
+        // A shift of one frame would give errors up to 0.013.
+        let expected = sine(100.0, 48000.0, 10884);
+        for (value, expected) in wave_out[0][200..10600].iter().zip(&expected[200..10600]) {
+            assert!((value - expected).abs() < 0.007, "{} {}", value, expected);
+        }
+    }
+
+    #[test]
+    fn resample_clip_downsample() {
+        let wave_in = [sine(100.0, 48000.0, 9999)];
+        let wave_out = resample_all(&wave_in, 48000, 32000, ResampleQuality::Draft).unwrap();
+        assert_eq!(wave_out[0].len(), 6666);
+        // A shift of one frame would give errors up to 0.02.
+        let expected = sine(100.0, 32000.0, 6666);
+        for (value, expected) in wave_out[0][200..6400].iter().zip(&expected[200..6400]) {
+            assert!((value - expected).abs() < 0.011, "{} {}", value, expected);
+        }
+    }
+
+    #[test]
+    fn resample_clip_same_rate() {
+        let wave_in = vec![vec![1.0f32, 2.0, 3.0], vec![4.0, 5.0]];
+        let wave_out = resample_all(&wave_in, 44100, 44100, ResampleQuality::High).unwrap();
+        assert_eq!(wave_out, vec![vec![1.0, 2.0], vec![4.0, 5.0]]);
+    }
+
+    #[test]
+    fn resample_clip_invalid_rate() {
+        let wave_in = vec![vec![0.0f64; 10]];
+        let result = resample_all(&wave_in, 0, 44100, ResampleQuality::Draft);
+        assert!(matches!(
+            result,
+            Err(ResamplerConstructionError::InvalidSampleRate { .. })
+        ));
+    }
+}
@@ -0,0 +1,384 @@
+use crate::adapters::{AudioInput, AudioOutput};
+use crate::Sample;
+use std::marker::PhantomData;
+
+/// The trait governing an integer PCM sample.
+///
+/// Integer samples are converted to and from floating point by scaling
+/// with `2^(BITS-1)`, so that the full integer range maps to the range -1.0 to +1.0.
+///
+/// There are three types which implements this trait:
+/// * [i16]
+/// * [I24]
+/// * [i32]
+pub trait PcmSample: Copy {
+    /// The number of bits of the sample format.
+    const BITS: u32;
+
+    /// Convert the sample to an [i32].
+    fn to_i32(self) -> i32;
+
+    /// Convert an [i32] to a sample.
+    /// The value must be within the range of the sample format.
+    fn from_i32(value: i32) -> Self;
+}
+
+impl PcmSample for i16 {
+    const BITS: u32 = 16;
+
+    fn to_i32(self) -> i32 {
+        self as i32
+    }
+
+    fn from_i32(value: i32) -> Self {
+        value as i16
+    }
+}
+
+impl PcmSample for i32 {
+    const BITS: u32 = 32;
+
+    fn to_i32(self) -> i32 {
+        self
+    }
+
+    fn from_i32(value: i32) -> Self {
+        value
+    }
+}
+
+/// A signed 24-bit integer sample, stored in the lower 24 bits of an [i32].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct I24(i32);
+
+impl I24 {
+    /// The smallest value of a 24-bit sample.
+    pub const MIN: I24 = I24(-(1 << 23));
+    /// The largest value of a 24-bit sample.
+    pub const MAX: I24 = I24((1 << 23) - 1);
+
+    /// Create a new sample from an [i32].
+    /// Returns `None` if the value is outside the range of a 24-bit sample.
+    pub fn new(value: i32) -> Option<Self> {
+        if (Self::MIN.0..=Self::MAX.0).contains(&value) {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    /// Get the value as an [i32].
+    pub fn value(self) -> i32 {
+        self.0
+    }
+
+    /// Create a sample from three bytes in little-endian order.
+    pub fn from_le_bytes(bytes: [u8; 3]) -> Self {
+        Self(i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8)
+    }
+
+    /// Get the sample as three bytes in little-endian order.
+    pub fn to_le_bytes(self) -> [u8; 3] {
+        let bytes = self.0.to_le_bytes();
+        [bytes[0], bytes[1], bytes[2]]
+    }
+}
+
+impl From<I24> for i32 {
+    fn from(value: I24) -> Self {
+        value.0
+    }
+}
+
+impl PcmSample for I24 {
+    const BITS: u32 = 24;
+
+    fn to_i32(self) -> i32 {
+        self.0
+    }
+
+    fn from_i32(value: i32) -> Self {
+        Self(value)
+    }
+}
+
+/// The quantization stage used when writing floating point values to integer samples.
+///
+/// The value to quantize is given in units of the least significant bit of the output format,
+/// and the quantizer returns the value rounded to an integer.
+/// A dithering quantizer adds noise before rounding,
+/// and may keep state per channel, for example to shape the spectrum of the added noise.
+/// See [Ditherer](crate::Ditherer) for a quantizer with dither and noise shaping.
+pub trait Dither {
+    /// Quantize a value for the given channel.
+    fn quantize(&mut self, channel: usize, value: f64) -> f64;
+}
+
+impl<D: Dither + ?Sized> Dither for &mut D {
+    fn quantize(&mut self, channel: usize, value: f64) -> f64 {
+        (**self).quantize(channel, value)
+    }
+}
+
+/// A quantizer that rounds to the nearest integer, without adding any dither.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoDither;
+
+impl Dither for NoDither {
+    fn quantize(&mut self, _channel: usize, value: f64) -> f64 {
+        value.round()
+    }
+}
+
+/// Scale factor between floating point values and integer samples.
+fn scale<P: PcmSample>() -> f64 {
+    (1u64 << (P::BITS - 1)) as f64
+}
+
+/// A wrapper that reads integer samples from an [AudioInput]
+/// and presents them as floating point values.
+///
+/// The samples are scaled so that the full integer range maps to the range -1.0 to +1.0.
+/// The wrapped input can be any [AudioInput] of a [PcmSample] type,
+/// for example a `Vec<Vec<i16>>` or an [InterleavedSlice](crate::InterleavedSlice) of [I24].
+pub struct PcmInput<P, I> {
+    input: I,
+    _phantom: PhantomData<P>,
+}
+
+impl<P, I> PcmInput<P, I>
+where
+    P: PcmSample,
+    I: AudioInput<P>,
+{
+    /// Wrap an input of integer samples.
+    pub fn new(input: I) -> Self {
+        Self {
+            input,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get back the wrapped input.
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<T, P, I> AudioInput<T> for PcmInput<P, I>
+where
+    T: Sample,
+    P: PcmSample,
+    I: AudioInput<P>,
+{
+    fn nbr_channels(&self) -> usize {
+        self.input.nbr_channels()
+    }
+
+    fn nbr_frames(&self, channel: usize) -> usize {
+        self.input.nbr_frames(channel)
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        let value = self.input.read_sample(channel, frame).to_i32() as f64 / scale::<P>();
+        T::coerce(value)
+    }
+}
+
+/// A wrapper that converts floating point values to integer samples
+/// and writes them to an [AudioOutput].
+///
+/// The values are scaled so that the range -1.0 to +1.0 maps to the full integer range.
+/// They are then quantized using a [Dither], and values outside the range
+/// of the sample format are clipped.
+/// The number of clipped samples is counted, and can be read with
+/// [clipped_samples](PcmOutput::clipped_samples).
+pub struct PcmOutput<P, O, D = NoDither> {
+    output: O,
+    dither: D,
+    clipped: usize,
+    _phantom: PhantomData<P>,
+}
+
+impl<P, O> PcmOutput<P, O, NoDither>
+where
+    P: PcmSample,
+    O: AudioOutput<P>,
+{
+    /// Wrap an output of integer samples, rounding the values to the nearest integer.
+    pub fn new(output: O) -> Self {
+        Self::with_dither(output, NoDither)
+    }
+}
+
+impl<P, O, D> PcmOutput<P, O, D>
+where
+    P: PcmSample,
+    O: AudioOutput<P>,
+    D: Dither,
+{
+    /// Wrap an output of integer samples, using the given [Dither] for quantization.
+    pub fn with_dither(output: O, dither: D) -> Self {
+        Self {
+            output,
+            dither,
+            clipped: 0,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get the number of samples that have been clipped since the wrapper was created,
+    /// or since the last call to [reset_clipped_samples](PcmOutput::reset_clipped_samples).
+    pub fn clipped_samples(&self) -> usize {
+        self.clipped
+    }
+
+    /// Reset the counter of clipped samples.
+    pub fn reset_clipped_samples(&mut self) {
+        self.clipped = 0;
+    }
+
+    /// Get back the wrapped output and dither.
+    pub fn into_inner(self) -> (O, D) {
+        (self.output, self.dither)
+    }
+}
+
+impl<T, P, O, D> AudioOutput<T> for PcmOutput<P, O, D>
+where
+    T: Sample,
+    P: PcmSample,
+    O: AudioOutput<P>,
+    D: Dither,
+{
+    fn nbr_channels(&self) -> usize {
+        self.output.nbr_channels()
+    }
+
+    fn nbr_frames(&self, channel: usize) -> usize {
+        self.output.nbr_frames(channel)
+    }
+
+    fn write_sample(&mut self, channel: usize, frame: usize, value: T) {
+        let max = scale::<P>() - 1.0;
+        let min = -scale::<P>();
+        let mut quantized = self.dither.quantize(channel, value.to_f64() * scale::<P>());
+        if quantized > max {
+            quantized = max;
+            self.clipped += 1;
+        } else if quantized < min {
+            quantized = min;
+            self.clipped += 1;
+        }
+        self.output
+            .write_sample(channel, frame, P::from_i32(quantized as i32));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dither, PcmInput, PcmOutput, I24};
+    use crate::{
+        AudioInput, AudioOutput, InterleavedSlice, Resampler, SincFixedIn,
+        SincInterpolationParameters, SincInterpolationType, SincPhase, WindowFunction,
+    };
+    use test_log::test;
+
+    #[test]
+    fn i24_bytes() {
+        let value = I24::new(-0x123456).unwrap();
+        assert_eq!(I24::from_le_bytes(value.to_le_bytes()), value);
+        assert_eq!(I24::from_le_bytes([0xff, 0xff, 0x7f]), I24::MAX);
+        assert_eq!(I24::from_le_bytes([0x00, 0x00, 0x80]), I24::MIN);
+        assert_eq!(I24::new(1 << 23), None);
+    }
+
+    #[test]
+    fn read_i16() {
+        let data = vec![vec![i16::MIN, 0, 16384], vec![i16::MAX, -1, 1]];
+        let input = PcmInput::new(&data);
+        let value: f64 = input.read_sample(0, 0);
+        assert_eq!(value, -1.0);
+        let value: f64 = input.read_sample(0, 2);
+        assert_eq!(value, 0.5);
+        let value: f32 = input.read_sample(1, 1);
+        assert_eq!(value, -1.0 / 32768.0);
+    }
+
+    #[test]
+    fn write_and_clip_i24() {
+        let mut data = [I24::default(); 6];
+        let mut output = PcmOutput::new(InterleavedSlice::new(&mut data[..], 2));
+        output.write_sample(0, 0, 0.5);
+        output.write_sample(1, 0, -0.25);
+        output.write_sample(0, 1, 1.5);
+        output.write_sample(1, 1, -1.5);
+        output.write_sample(0, 2, 1.0);
+        output.write_sample(1, 2, 0.4 / 8388608.0);
+        assert_eq!(output.clipped_samples(), 3);
+        let values: Vec<i32> = data.iter().map(|v| v.value()).collect();
+        assert_eq!(values, [4194304, -2097152, 8388607, -8388608, 8388607, 0]);
+    }
+
+    struct AddOne;
+
+    impl Dither for AddOne {
+        fn quantize(&mut self, _channel: usize, value: f64) -> f64 {
+            (value + 1.0).round()
+        }
+    }
+
+    #[test]
+    fn write_with_dither() {
+        let mut data = vec![vec![0i16; 2]];
+        let mut output = PcmOutput::with_dither(&mut data, AddOne);
+        output.copy_from_slice_to_channel(0, 0, &[0.0f32, -1.0]);
+        assert_eq!(output.clipped_samples(), 0);
+        assert_eq!(data, [[1, -32767]]);
+    }
+
+    #[test]
+    fn resample_i16_to_i24() {
+        let params = || SincInterpolationParameters {
+            sinc_len: 64,
+            f_cutoff: 0.9,
+            interpolation: SincInterpolationType::Linear,
+            oversampling_factor: 64,
+            window: WindowFunction::BlackmanHarris2,
+            phase: SincPhase::Linear,
+        };
+        let mut resampler = SincFixedIn::<f64>::new(1.5, 1.0, params(), 256, 2).unwrap();
+        let mut reference = SincFixedIn::<f64>::new(1.5, 1.0, params(), 256, 2).unwrap();
+        let data_in: Vec<i16> = (0..512)
+            .map(|n| (20000.0 * (n as f64 * 0.05).sin()) as i16)
+            .collect();
+        let float_in: Vec<Vec<f64>> = (0..2)
+            .map(|chan| {
+                data_in
+                    .iter()
+                    .skip(chan)
+                    .step_by(2)
+                    .map(|v| *v as f64 / 32768.0)
+                    .collect()
+            })
+            .collect();
+        let mut data_out = vec![I24::default(); 2 * resampler.output_frames_max()];
+        let mut output = PcmOutput::new(InterleavedSlice::new(&mut data_out[..], 2));
+        let (_, nbr_out) = resampler
+            .process_into_buffer(
+                &PcmInput::new(InterleavedSlice::new(&data_in[..], 2)),
+                &mut output,
+                None,
+            )
+            .unwrap();
+        assert_eq!(output.clipped_samples(), 0);
+        let float_out = reference.process(&float_in, None).unwrap();
+        assert_eq!(float_out[0].len(), nbr_out);
+        for frame in 0..nbr_out {
+            for chan in 0..2 {
+                let expected = (float_out[chan][frame] * 8388608.0).round() as i32;
+                assert_eq!(data_out[2 * frame + chan].value(), expected);
+            }
+        }
+    }
+}
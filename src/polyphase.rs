@@ -0,0 +1,415 @@
+use crate::adapters::{AudioInput, AudioOutput, ZeroPadded};
+use crate::asynchro_sinc::make_interpolator;
+use crate::error::{ResampleError, ResampleResult, ResamplerConstructionError};
+use crate::sinc_interpolator::SincInterpolator;
+use crate::{
+    flush_resampler, partial_input_frames, update_mask_from_buffers, validate_buffers,
+    validate_sample_rates, FrameCounter, Resampler, Sample, SincInterpolationParameters,
+};
+use num_integer as integer;
+use std::sync::Arc;
+
+/// A synchronous resampler that needs a fixed number of audio frames for input
+/// and returns a variable number of frames.
+///
+/// The resampling is done by polyphase FIR filtering.
+/// The ratio between the sample rates is reduced to L/M, where L is the output rate
+/// and M is the input rate divided by their greatest common divisor.
+/// The anti-aliasing filter is a windowed sinc, split into exactly L phases
+/// that are all calculated when the resampler is created.
+/// Each output frame is then calculated as the scalar product between the input
+/// and one of these phases, which makes the conversion exact without any interpolation
+/// between intermediate points.
+///
+/// The output is calculated frame by frame, which gives a much lower latency than
+/// the FFT resamplers, and this resampler is available also when the `fft_resampler`
+/// feature is disabled.
+/// The memory needed for the filters is proportional to L,
+/// which makes this resampler best suited for ratios between simple rates such as 44100 and 48000 Hz,
+/// where L is small.
+#[derive(Clone)]
+pub struct PolyphaseFixedIn<T> {
+    nbr_channels: usize,
+    chunk_size: usize,
+    max_chunk_size: usize,
+    interpolation_factor: usize,
+    decimation_factor: usize,
+    /// The position of the last output frame, in units of 1/L input frames
+    /// and relative to the start of the current chunk.
+    last_index: isize,
+    interpolator: Arc<dyn SincInterpolator<T>>,
+    buffer: Vec<Vec<T>>,
+    channel_mask: Vec<bool>,
+    frame_counter: FrameCounter,
+}
+
+/// Get the starting position, in units of 1/L input frames.
+/// Like for [SincFixedIn](crate::SincFixedIn), this aligns the end of the sinc with the first input frame
+/// when calculating the first output frame, which makes the output delay equal to the delay of the sinc.
+fn start_index(sinc_len: usize, interpolation_factor: usize, decimation_factor: usize) -> isize {
+    (1 - sinc_len as isize) * interpolation_factor as isize - decimation_factor as isize
+}
+
+impl<T> PolyphaseFixedIn<T>
+where
+    T: Sample,
+{
+    /// Create a new PolyphaseFixedIn.
+    ///
+    /// Parameters are:
+    /// - `sample_rate_input`: Input sample rate, must be > 0.
+    /// - `sample_rate_output`: Output sample rate, must be > 0.
+    /// - `parameters`: Parameters for the sinc filter, see `SincInterpolationParameters`.
+    ///   The `oversampling_factor` and `interpolation` fields are ignored,
+    ///   since the number of sincs is given by the ratio and no interpolation is needed.
+    /// - `chunk_size_in`: Size of input data in frames.
+    /// - `nbr_channels`: Number of channels in input/output.
+    pub fn new(
+        sample_rate_input: usize,
+        sample_rate_output: usize,
+        parameters: SincInterpolationParameters,
+        chunk_size_in: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        validate_sample_rates(sample_rate_input, sample_rate_output)?;
+
+        let gcd = integer::gcd(sample_rate_input, sample_rate_output);
+        let interpolation_factor = sample_rate_output / gcd;
+        let decimation_factor = sample_rate_input / gcd;
+        debug!(
+            "Create new PolyphaseFixedIn, sample_rate_input: {}, sample_rate_output: {}, chunk_size_in: {}, channels: {}, phases: {}, parameters: {:?}",
+            sample_rate_input, sample_rate_output, chunk_size_in, nbr_channels, interpolation_factor, parameters
+        );
+
+        let interpolator = make_interpolator(
+            parameters.sinc_len,
+            sample_rate_output as f64 / sample_rate_input as f64,
+            parameters.f_cutoff,
+            interpolation_factor,
+            parameters.window,
+            parameters.phase,
+        );
+        let sinc_len = interpolator.len();
+        let buffer = vec![vec![T::zero(); chunk_size_in + 2 * sinc_len]; nbr_channels];
+
+        Ok(PolyphaseFixedIn {
+            nbr_channels,
+            chunk_size: chunk_size_in,
+            max_chunk_size: chunk_size_in,
+            interpolation_factor,
+            decimation_factor,
+            last_index: start_index(sinc_len, interpolation_factor, decimation_factor),
+            interpolator,
+            buffer,
+            channel_mask: vec![true; nbr_channels],
+            frame_counter: FrameCounter::default(),
+        })
+    }
+
+    /// The resampling ratio, output frames per input frame.
+    fn ratio(&self) -> f64 {
+        self.interpolation_factor as f64 / self.decimation_factor as f64
+    }
+
+    /// The end of the range of positions that can be calculated from the current chunk,
+    /// in units of 1/L input frames.
+    fn end_index(&self) -> isize {
+        (self.chunk_size as isize - self.interpolator.len() as isize)
+            * self.interpolation_factor as isize
+    }
+}
+
+impl<T> Resampler<T> for PolyphaseFixedIn<T>
+where
+    T: Sample,
+{
+    fn process_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: &Vin,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        if let Some(mask) = active_channels_mask {
+            self.channel_mask.copy_from_slice(mask);
+        } else {
+            update_mask_from_buffers(&mut self.channel_mask);
+        };
+
+        let needed_len = self.output_frames_next();
+
+        validate_buffers(
+            wave_in,
+            wave_out,
+            &self.channel_mask,
+            self.nbr_channels,
+            self.chunk_size,
+            needed_len,
+        )?;
+
+        let sinc_len = self.interpolator.len();
+        let factor = self.interpolation_factor as isize;
+        let step = self.decimation_factor as isize;
+
+        // Update buffer with new data.
+        for buf in self.buffer.iter_mut() {
+            buf.copy_within(self.chunk_size..self.chunk_size + 2 * sinc_len, 0);
+        }
+
+        for (chan, active) in self.channel_mask.iter().enumerate() {
+            if *active {
+                wave_in.copy_from_channel_to_slice(
+                    chan,
+                    0,
+                    &mut self.buffer[chan][2 * sinc_len..2 * sinc_len + self.chunk_size],
+                );
+            }
+        }
+
+        let mut idx = self.last_index;
+        for n in 0..needed_len {
+            idx += step;
+            let index = (idx.div_euclid(factor) + 2 * sinc_len as isize) as usize;
+            let subindex = idx.rem_euclid(factor) as usize;
+            for (chan, active) in self.channel_mask.iter().enumerate() {
+                if *active {
+                    let point = self.interpolator.get_sinc_interpolated(
+                        &self.buffer[chan],
+                        index,
+                        subindex,
+                    );
+                    wave_out.write_sample(chan, n, point);
+                }
+            }
+        }
+
+        // Store last index for next iteration.
+        self.last_index = idx - self.chunk_size as isize * factor;
+        self.frame_counter
+            .add(self.chunk_size, needed_len, self.ratio());
+        trace!(
+            "Resampling channels {:?}, {} frames in, {} frames out",
+            active_channels_mask,
+            self.chunk_size,
+            needed_len,
+        );
+        Ok((self.chunk_size, needed_len))
+    }
+
+    fn input_frames_max(&self) -> usize {
+        self.max_chunk_size
+    }
+
+    fn input_frames_next(&self) -> usize {
+        self.chunk_size
+    }
+
+    fn nbr_channels(&self) -> usize {
+        self.nbr_channels
+    }
+
+    fn output_frames_max(&self) -> usize {
+        (self.max_chunk_size * self.interpolation_factor + self.decimation_factor - 1)
+            / self.decimation_factor
+    }
+
+    fn output_frames_next(&self) -> usize {
+        // The number of steps that stay before the end of the usable range.
+        ((self.end_index() - self.last_index - 1) / self.decimation_factor as isize) as usize
+    }
+
+    fn output_delay(&self) -> usize {
+        (self.interpolator.delay() * self.ratio()).round() as usize
+    }
+
+    fn process_partial_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let frames = partial_input_frames(wave_in, self.input_frames_next());
+        let (frames_in, frames_out) = self.process_into_buffer(
+            &ZeroPadded::new(wave_in, self.nbr_channels),
+            wave_out,
+            active_channels_mask,
+        )?;
+        self.frame_counter
+            .remove_padding(frames_in - frames, self.ratio());
+        Ok((frames_in, frames_out))
+    }
+
+    fn output_frames_remaining(&self) -> usize {
+        self.frame_counter.remaining(self.output_delay())
+    }
+
+    fn flush_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        flush_resampler(
+            self,
+            |resampler| resampler.frame_counter,
+            wave_in,
+            wave_out,
+            active_channels_mask,
+        )
+    }
+
+    /// Update the resample ratio. This is not supported by this resampler and
+    /// always returns [ResampleError::SyncNotAdjustable].
+    fn set_resample_ratio(&mut self, _new_ratio: f64, _ramp: bool) -> ResampleResult<()> {
+        Err(ResampleError::SyncNotAdjustable)
+    }
+
+    /// Update the resample ratio relative to the original one. This is not
+    /// supported by this resampler and always returns [ResampleError::SyncNotAdjustable].
+    fn set_resample_ratio_relative(&mut self, _rel_ratio: f64, _ramp: bool) -> ResampleResult<()> {
+        Err(ResampleError::SyncNotAdjustable)
+    }
+
+    fn reset(&mut self) {
+        self.frame_counter.reset();
+        self.buffer
+            .iter_mut()
+            .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
+        self.channel_mask.iter_mut().for_each(|val| *val = true);
+        self.last_index = start_index(
+            self.interpolator.len(),
+            self.interpolation_factor,
+            self.decimation_factor,
+        );
+        self.chunk_size = self.max_chunk_size;
+    }
+
+    fn set_chunk_size(&mut self, chunksize: usize) -> ResampleResult<()> {
+        if chunksize > self.max_chunk_size || chunksize == 0 {
+            return Err(ResampleError::InvalidChunkSize {
+                max: self.max_chunk_size,
+                requested: chunksize,
+            });
+        }
+        self.chunk_size = chunksize;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::check_output;
+    use crate::{PolyphaseFixedIn, ResampleError, Resampler, SincInterpolationParameters};
+    use std::f64::consts::PI;
+    use test_log::test;
+
+    #[test]
+    fn poly_output() {
+        let params = SincInterpolationParameters::draft();
+        let mut resampler = PolyphaseFixedIn::<f64>::new(44100, 48000, params, 1024, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn poly_output_downsample() {
+        let params = SincInterpolationParameters::draft();
+        let mut resampler = PolyphaseFixedIn::<f64>::new(48000, 44100, params, 1024, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    // Processing in small chunks gives the same output as processing in large chunks.
+    #[test]
+    fn poly_small_chunks() {
+        let mut large = PolyphaseFixedIn::<f64>::new(
+            44100,
+            48000,
+            SincInterpolationParameters::draft(),
+            1024,
+            1,
+        )
+        .unwrap();
+        let mut small =
+            PolyphaseFixedIn::<f64>::new(44100, 48000, SincInterpolationParameters::draft(), 16, 1)
+                .unwrap();
+        let input = (0..4096)
+            .map(|n| (2.0 * PI * 1000.0 * n as f64 / 44100.0).sin())
+            .collect::<Vec<f64>>();
+        let mut output_large = Vec::new();
+        for chunk in input.chunks(1024) {
+            output_large.extend(large.process(&[chunk], None).unwrap().remove(0));
+        }
+        let mut output_small = Vec::new();
+        for chunk in input.chunks(16) {
+            output_small.extend(small.process(&[chunk], None).unwrap().remove(0));
+        }
+        assert!(output_large.len().min(output_small.len()) > 4000);
+        for (n, (a, b)) in output_large.iter().zip(output_small.iter()).enumerate() {
+            assert!((a - b).abs() < 1.0e-12, "Frame {}: {} {}", n, a, b);
+        }
+    }
+
+    // The number of output frames follows the ratio exactly,
+    // every 147 chunks of 100 frames give 16000 output frames.
+    #[test]
+    fn poly_exact_ratio() {
+        let params = SincInterpolationParameters::draft();
+        let mut resampler = PolyphaseFixedIn::<f32>::new(44100, 48000, params, 100, 1).unwrap();
+        let max = resampler.output_frames_max();
+        let input = vec![vec![0.0f32; 100]];
+        let mut total_out = 0;
+        let mut totals = Vec::new();
+        for n in 1..=147 * 3 {
+            let nbr_out = resampler.output_frames_next();
+            assert!(nbr_out <= max);
+            let out = resampler.process(&input, None).unwrap();
+            assert_eq!(out[0].len(), nbr_out);
+            total_out += nbr_out;
+            if n % 147 == 0 {
+                totals.push(total_out);
+            }
+        }
+        assert_eq!(totals[1] - totals[0], 16000);
+        assert_eq!(totals[2] - totals[1], 16000);
+    }
+
+    #[test]
+    fn poly_sine() {
+        let params = SincInterpolationParameters::balanced();
+        let mut resampler = PolyphaseFixedIn::<f64>::new(44100, 48000, params, 1024, 1).unwrap();
+        let freq = 1000.0;
+        let mut output = Vec::new();
+        for chunk in 0..10 {
+            let input = vec![(0..1024)
+                .map(|n| (2.0 * PI * freq * (chunk * 1024 + n) as f64 / 44100.0).sin())
+                .collect::<Vec<f64>>()];
+            output.extend(resampler.process(&input, None).unwrap().remove(0));
+        }
+        let delay = resampler.output_delay();
+        let delay_exact = resampler.interpolator.delay() * 48000.0 / 44100.0;
+        for (n, value) in output.iter().enumerate().skip(2 * delay).take(8000) {
+            let expected = (2.0 * PI * freq * (n as f64 - delay_exact) / 48000.0).sin();
+            assert!((value - expected).abs() < 1.0e-3, "{} {}", value, expected);
+        }
+    }
+
+    #[test]
+    fn poly_not_adjustable() {
+        let params = SincInterpolationParameters::draft();
+        let mut resampler = PolyphaseFixedIn::<f64>::new(44100, 48000, params, 1024, 2).unwrap();
+        assert!(matches!(
+            resampler.set_resample_ratio(1.1, false),
+            Err(ResampleError::SyncNotAdjustable)
+        ));
+    }
+}
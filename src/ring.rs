@@ -0,0 +1,466 @@
+use crate::adapters::{AudioInput, AudioOutput, OutputWindow};
+use crate::buffered::{insufficient_output, output_room, validate_output};
+use crate::error::{ResampleError, ResampleResult};
+use crate::{Resampler, Sample};
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A single producer, single consumer ring buffer of audio frames.
+///
+/// The read and write positions run from 0 to twice the capacity,
+/// so that a full buffer can be told apart from an empty one.
+struct RingBuffer<T> {
+    data: Box<[UnsafeCell<T>]>,
+    channels: usize,
+    capacity: usize,
+    write_pos: AtomicUsize,
+    read_pos: AtomicUsize,
+}
+
+// SAFETY: The producer only writes to the frames that are free,
+// and the consumer only reads the frames that have been written.
+// A frame changes owner when the corresponding position is published
+// with a release store, and loaded with an acquire load.
+unsafe impl<T: Send> Sync for RingBuffer<T> {}
+
+impl<T: Sample> RingBuffer<T> {
+    fn new(channels: usize, capacity: usize) -> Self {
+        let data = (0..channels * capacity)
+            .map(|_| UnsafeCell::new(T::zero()))
+            .collect();
+        RingBuffer {
+            data,
+            channels,
+            capacity,
+            write_pos: AtomicUsize::new(0),
+            read_pos: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the number of frames between two positions.
+    fn distance(&self, from: usize, to: usize) -> usize {
+        (to + 2 * self.capacity - from) % (2 * self.capacity)
+    }
+
+    /// Move a position forward by a number of frames.
+    fn advance(&self, pos: usize, frames: usize) -> usize {
+        (pos + frames) % (2 * self.capacity)
+    }
+
+    /// Get the index of a sample in the data.
+    fn index(&self, pos: usize, channel: usize) -> usize {
+        (pos % self.capacity) * self.channels + channel
+    }
+}
+
+/// The frames in a [RingBuffer] that are available to the consumer.
+struct RingReader<'a, T> {
+    ring: &'a RingBuffer<T>,
+    start: usize,
+    frames: usize,
+}
+
+impl<'a, T: Sample> AudioInput<T> for RingReader<'a, T> {
+    fn nbr_channels(&self) -> usize {
+        self.ring.channels
+    }
+
+    fn nbr_frames(&self, _channel: usize) -> usize {
+        self.frames
+    }
+
+    fn read_sample(&self, channel: usize, frame: usize) -> T {
+        let index = self
+            .ring
+            .index(self.ring.advance(self.start, frame), channel);
+        // SAFETY: The reader only covers frames that were published by the producer,
+        // and that the producer will not write to until the consumer releases them.
+        unsafe { *self.ring.data[index].get() }
+    }
+
+    fn copy_from_channel_to_slice(&self, channel: usize, skip: usize, slice: &mut [T]) {
+        // Copy in two runs, split where the frames wrap around the end of the buffer.
+        let ring = self.ring;
+        let first = ring.advance(self.start, skip) % ring.capacity;
+        let (head, tail) = slice.split_at_mut(slice.len().min(ring.capacity - first));
+        for (values, pos) in [(head, first), (tail, 0)] {
+            let cells = ring.data[ring.index(pos, channel)..]
+                .iter()
+                .step_by(ring.channels);
+            for (value, cell) in values.iter_mut().zip(cells) {
+                // SAFETY: See `read_sample`.
+                *value = unsafe { *cell.get() };
+            }
+        }
+    }
+}
+
+/// Create a lock-free ring buffer that feeds a resampler.
+///
+/// Returns a [RingProducer] that pushes input frames into the ring buffer,
+/// and a [RingConsumer] that owns the resampler and processes the frames from the ring buffer.
+/// The two halves can be moved to different threads, typically with the producer
+/// in the callback of an audio API, and the consumer in a processing loop.
+/// Neither half locks or allocates when pushing or processing.
+///
+/// The ring buffer has room for `capacity` frames,
+/// but at least for the largest chunk the resampler can need,
+/// as given by [input_frames_max](Resampler::input_frames_max).
+///
+/// ```
+/// use rubato::{ring_resampler, FastFixedIn, PolynomialDegree, Resampler};
+/// let resampler = FastFixedIn::<f32>::new(1.5, 1.0, PolynomialDegree::Cubic, 512, 2).unwrap();
+/// let (mut producer, mut consumer) = ring_resampler(resampler, 4096);
+/// let mut wave_out = consumer.resampler().output_buffer_allocate(true);
+///
+/// // In the audio callback.
+/// let pushed = producer.push(&vec![vec![0.0f32; 300]; 2]).unwrap();
+/// assert_eq!(pushed, 300);
+///
+/// // In the processing loop.
+/// while let Some((_, frames_out)) = consumer.process_into_buffer(&mut wave_out, None).unwrap() {
+///     // Use the first `frames_out` frames of `wave_out`.
+/// }
+/// ```
+pub fn ring_resampler<T, R>(resampler: R, capacity: usize) -> (RingProducer<T>, RingConsumer<T, R>)
+where
+    T: Sample,
+    R: Resampler<T>,
+{
+    let capacity = capacity.max(resampler.input_frames_max()).max(1);
+    let ring = Arc::new(RingBuffer::new(resampler.nbr_channels(), capacity));
+    let producer = RingProducer { ring: ring.clone() };
+    let consumer = RingConsumer { ring, resampler };
+    (producer, consumer)
+}
+
+/// The producer half of a ring buffer made by [ring_resampler].
+pub struct RingProducer<T> {
+    ring: Arc<RingBuffer<T>>,
+}
+
+impl<T: Sample> RingProducer<T> {
+    /// Push input frames into the ring buffer.
+    ///
+    /// The number of frames is given by the shortest channel of the input.
+    /// If the ring buffer does not have room for all of them, only the frames that fit are pushed.
+    /// The number of frames pushed is returned.
+    pub fn push<Vin>(&mut self, wave_in: &Vin) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+    {
+        let ring = &*self.ring;
+        if wave_in.nbr_channels() != ring.channels {
+            return Err(ResampleError::WrongNumberOfInputChannels {
+                expected: ring.channels,
+                actual: wave_in.nbr_channels(),
+            });
+        }
+        let frames = (0..ring.channels)
+            .map(|chan| wave_in.nbr_frames(chan))
+            .min()
+            .unwrap_or(0)
+            .min(self.free_frames());
+        let write_pos = ring.write_pos.load(Ordering::Relaxed);
+        for frame in 0..frames {
+            let pos = ring.advance(write_pos, frame);
+            for chan in 0..ring.channels {
+                // SAFETY: The frames up to `free_frames()` after the write position
+                // have been released by the consumer, and are not read until published.
+                unsafe {
+                    *ring.data[ring.index(pos, chan)].get() = wave_in.read_sample(chan, frame);
+                }
+            }
+        }
+        ring.write_pos
+            .store(ring.advance(write_pos, frames), Ordering::Release);
+        Ok(frames)
+    }
+
+    /// Get the number of frames that can currently be pushed.
+    pub fn free_frames(&self) -> usize {
+        let ring = &*self.ring;
+        let used = ring.distance(
+            ring.read_pos.load(Ordering::Acquire),
+            ring.write_pos.load(Ordering::Relaxed),
+        );
+        ring.capacity - used
+    }
+}
+
+/// The consumer half of a ring buffer made by [ring_resampler],
+/// that owns the resampler.
+pub struct RingConsumer<T, R> {
+    ring: Arc<RingBuffer<T>>,
+    resampler: R,
+}
+
+impl<T, R> RingConsumer<T, R>
+where
+    T: Sample,
+    R: Resampler<T>,
+{
+    /// Get the number of frames that are available in the ring buffer.
+    pub fn available_frames(&self) -> usize {
+        let ring = &*self.ring;
+        ring.distance(
+            ring.read_pos.load(Ordering::Relaxed),
+            ring.write_pos.load(Ordering::Acquire),
+        )
+    }
+
+    /// Process one chunk, if the ring buffer holds enough frames for it,
+    /// and write the output to a pre-allocated output buffer.
+    ///
+    /// This works like [Resampler::process_into_buffer],
+    /// but reads the input directly from the ring buffer.
+    /// If there are fewer than [input_frames_next](Resampler::input_frames_next) frames available,
+    /// nothing is processed and `None` is returned.
+    /// Otherwise, the number of input frames read and output frames written are returned.
+    pub fn process_into_buffer<Vout>(
+        &mut self,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<Option<(usize, usize)>>
+    where
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        if self.available_frames() < self.resampler.input_frames_next() {
+            return Ok(None);
+        }
+        let ring = &*self.ring;
+        let read_pos = ring.read_pos.load(Ordering::Relaxed);
+        let reader = RingReader {
+            ring,
+            start: read_pos,
+            frames: self.resampler.input_frames_next(),
+        };
+        let (frames_in, frames_out) =
+            self.resampler
+                .process_into_buffer(&reader, wave_out, active_channels_mask)?;
+        ring.read_pos
+            .store(ring.advance(read_pos, frames_in), Ordering::Release);
+        Ok(Some((frames_in, frames_out)))
+    }
+
+    /// Flush the resampler at the end of a stream, and write the remaining output frames
+    /// to a pre-allocated output buffer.
+    ///
+    /// All full chunks remaining in the ring buffer are processed,
+    /// and the resampler is then flushed with [Resampler::flush_into_buffer],
+    /// using the last frames in the ring buffer as the final input.
+    /// The output buffer must have room for all the remaining frames,
+    /// otherwise a [ResampleError] is returned.
+    /// This is checked before anything is processed, using an upper bound
+    /// of the number of remaining frames.
+    /// The producer should not push any more frames.
+    /// The number of frames written is returned.
+    pub fn flush_into_buffer<Vout>(
+        &mut self,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        validate_output(
+            self.resampler.nbr_channels(),
+            wave_out,
+            active_channels_mask,
+        )?;
+        let room = output_room(wave_out, active_channels_mask);
+        let needed = self.flush_frames_max();
+        if room < needed {
+            return Err(insufficient_output(wave_out, active_channels_mask, needed));
+        }
+        let ring = &*self.ring;
+        let mut frames_written = 0;
+        loop {
+            let read_pos = ring.read_pos.load(Ordering::Relaxed);
+            let available = self.available_frames();
+            let frames_next = self.resampler.input_frames_next();
+            let reader = RingReader {
+                ring,
+                start: read_pos,
+                frames: available.min(frames_next),
+            };
+            let mut window = OutputWindow::new(&mut *wave_out, frames_written, room);
+            if available < frames_next {
+                frames_written += self.resampler.flush_into_buffer(
+                    Some(&reader),
+                    &mut window,
+                    active_channels_mask,
+                )?;
+                ring.read_pos
+                    .store(ring.advance(read_pos, available), Ordering::Release);
+                return Ok(frames_written);
+            }
+            let (frames_in, frames_out) =
+                self.resampler
+                    .process_into_buffer(&reader, &mut window, active_channels_mask)?;
+            frames_written += frames_out;
+            ring.read_pos
+                .store(ring.advance(read_pos, frames_in), Ordering::Release);
+        }
+    }
+
+    /// Get a reference to the resampler.
+    pub fn resampler(&self) -> &R {
+        &self.resampler
+    }
+
+    /// Get a mutable reference to the resampler,
+    /// for example for updating the resampling ratio.
+    pub fn resampler_mut(&mut self) -> &mut R {
+        &mut self.resampler
+    }
+
+    /// Get an upper bound of the number of frames written when flushing,
+    /// counted in the same way as for [BufferedResampler::flush_into_buffer](crate::BufferedResampler::flush_into_buffer).
+    fn flush_frames_max(&self) -> usize {
+        let available = self.available_frames();
+        let frames_next = self.resampler.input_frames_next();
+        let remaining = self.resampler.output_frames_remaining();
+        if available < frames_next {
+            return remaining + self.resampler.output_frames_max();
+        }
+        let chunks = (available + frames_next - 1) / frames_next;
+        remaining + (chunks + 1) * self.resampler.output_frames_max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RingBuffer, RingReader};
+    use crate::adapters::AudioInput;
+    use crate::tests::{reference_output, sine_stream};
+    use crate::{ring_resampler, FastFixedIn, PolynomialDegree, Resampler};
+    use crate::{SincFixedOut, SincInterpolationParameters};
+    use test_log::test;
+
+    #[test]
+    fn ring_same_thread() {
+        let params = SincInterpolationParameters::draft();
+        let resampler = SincFixedOut::<f64>::new(0.8, 1.0, params, 256, 2).unwrap();
+        let stream = sine_stream(5000);
+        let expected = reference_output(resampler.clone(), &stream);
+
+        let (mut producer, mut consumer) = ring_resampler(resampler, 600);
+        let mut wave_out = consumer.resampler().output_buffer_allocate(true);
+        let mut output = Vec::new();
+        let mut pos = 0;
+        for size in [1, 100, 333, 7, 2000, 64].iter().cycle() {
+            if pos >= stream.len() {
+                break;
+            }
+            let chunk = &stream[pos..stream.len().min(pos + size)];
+            let pushed = producer.push(&[chunk, chunk]).unwrap();
+            assert!(pushed <= chunk.len());
+            pos += pushed;
+            while let Some((_, frames_out)) =
+                consumer.process_into_buffer(&mut wave_out, None).unwrap()
+            {
+                assert_eq!(wave_out[0][..frames_out], wave_out[1][..frames_out]);
+                output.extend_from_slice(&wave_out[0][..frames_out]);
+            }
+        }
+        let mut wave_out = vec![vec![0.0; 2000]; 2];
+        let frames_out = consumer.flush_into_buffer(&mut wave_out, None).unwrap();
+        output.extend_from_slice(&wave_out[0][..frames_out]);
+        assert_eq!(consumer.available_frames(), 0);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn ring_threads() {
+        let resampler = FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 128, 2).unwrap();
+        let stream = sine_stream(20000);
+        let expected = reference_output(resampler.clone(), &stream);
+
+        let (mut producer, mut consumer) = ring_resampler(resampler, 256);
+        let input = stream.clone();
+        let producer_thread = std::thread::spawn(move || {
+            let mut pos = 0;
+            while pos < input.len() {
+                let chunk = &input[pos..input.len().min(pos + 100)];
+                pos += producer.push(&[chunk, chunk]).unwrap();
+                std::thread::yield_now();
+            }
+        });
+        let mut wave_out = consumer.resampler().output_buffer_allocate(true);
+        let mut output = Vec::new();
+        let mut frames_read = 0;
+        while frames_read + consumer.resampler().input_frames_next() <= stream.len() {
+            match consumer.process_into_buffer(&mut wave_out, None).unwrap() {
+                Some((frames_in, frames_out)) => {
+                    frames_read += frames_in;
+                    output.extend_from_slice(&wave_out[0][..frames_out]);
+                }
+                None => std::thread::yield_now(),
+            }
+        }
+        producer_thread.join().unwrap();
+        let mut wave_out = vec![vec![0.0; 2000]; 2];
+        let frames_out = consumer.flush_into_buffer(&mut wave_out, None).unwrap();
+        output.extend_from_slice(&wave_out[0][..frames_out]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn ring_flush_several_chunks() {
+        let resampler = FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 128, 2).unwrap();
+        let stream = sine_stream(1000);
+        let expected = reference_output(resampler.clone(), &stream);
+
+        let (mut producer, mut consumer) = ring_resampler(resampler, 1024);
+        assert_eq!(producer.push(&[&stream, &stream]).unwrap(), stream.len());
+        let mut wave_out = vec![vec![0.0; 2000]; 2];
+        let frames_out = consumer.flush_into_buffer(&mut wave_out, None).unwrap();
+        assert_eq!(consumer.available_frames(), 0);
+        assert_eq!(wave_out[0][..frames_out], wave_out[1][..frames_out]);
+        assert_eq!(wave_out[0][..frames_out], expected[..]);
+    }
+
+    #[test]
+    fn ring_flush_too_small_output() {
+        let resampler = FastFixedIn::<f64>::new(1.5, 1.0, PolynomialDegree::Cubic, 128, 1).unwrap();
+        let (mut producer, mut consumer) = ring_resampler(resampler, 1024);
+        producer.push(&[vec![0.5; 1000]]).unwrap();
+        let mut wave_out = vec![vec![0.0; 100]];
+        assert!(consumer.flush_into_buffer(&mut wave_out, None).is_err());
+        assert_eq!(consumer.available_frames(), 1000);
+    }
+
+    #[test]
+    fn ring_full() {
+        let resampler =
+            FastFixedIn::<f64>::new(1.0, 1.0, PolynomialDegree::Linear, 128, 1).unwrap();
+        let (mut producer, consumer) = ring_resampler(resampler, 200);
+        assert_eq!(producer.push(&[vec![1.0; 150]]).unwrap(), 150);
+        assert_eq!(producer.free_frames(), 50);
+        assert_eq!(producer.push(&[vec![1.0; 150]]).unwrap(), 50);
+        assert_eq!(producer.free_frames(), 0);
+        assert_eq!(consumer.available_frames(), 200);
+        assert!(producer.push(&[vec![1.0; 10], vec![1.0; 10]]).is_err());
+    }
+
+    #[test]
+    fn ring_reader_wraps() {
+        let ring = RingBuffer::<f64>::new(2, 10);
+        for (index, cell) in ring.data.iter().enumerate() {
+            unsafe { *cell.get() = index as f64 };
+        }
+        let reader = RingReader {
+            ring: &ring,
+            start: 17,
+            frames: 10,
+        };
+        for skip in 0..4 {
+            let mut copied = vec![0.0; 10 - skip];
+            reader.copy_from_channel_to_slice(1, skip, &mut copied);
+            let expected: Vec<f64> = (skip..10).map(|n| reader.read_sample(1, n)).collect();
+            assert_eq!(copied, expected);
+        }
+    }
+}
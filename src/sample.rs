@@ -48,6 +48,9 @@ where
     /// Calculate the cosine of `self`.
     fn cos(self) -> Self;
 
+    /// Convert `self` into an [f64].
+    fn to_f64(self) -> f64;
+
     /// Coerce `value` into the current type.
     ///
     /// Coercions are governed through the private `CoerceFrom` trait.
@@ -69,6 +72,10 @@ impl Sample for f32 {
     fn cos(self) -> Self {
         f32::cos(self)
     }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
 }
 
 impl Sample for f64 {
@@ -81,6 +88,10 @@ impl Sample for f64 {
     fn cos(self) -> Self {
         f64::cos(self)
     }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
 }
 
 /// The trait used to coerce a value infallibly from one type to another.
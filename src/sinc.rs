@@ -1,5 +1,6 @@
 use crate::windows::{make_window, WindowFunction};
 use crate::Sample;
+use std::f64::consts::PI;
 
 /// Helper function: sinc(x) = sin(pi*x)/(pi*x).
 pub fn sinc<T>(value: T) -> T
@@ -13,12 +14,43 @@ where
     }
 }
 
+/// The phase response of the sinc filters.
+///
+/// A linear phase filter delays all frequencies equally, by half the length of the sinc.
+/// Its impulse response is symmetric, meaning that it rings both before and after an impulse.
+/// A minimum phase filter has the same magnitude response, but the shortest possible delay.
+/// The delay then varies with frequency, and all the ringing comes after the impulse.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SincPhase {
+    /// Linear phase, with a delay of half the sinc length.
+    Linear,
+    /// Minimum phase, for the lowest possible delay.
+    Minimum,
+    /// A phase response between linear and minimum phase.
+    /// The value gives the position between linear phase at 0.0 and minimum phase at 1.0,
+    /// and is clamped to this range.
+    /// The delay decreases linearly from that of linear phase to that of minimum phase.
+    Intermediate(f32),
+}
+
+impl SincPhase {
+    /// Get the position between linear phase at 0.0 and minimum phase at 1.0.
+    fn minimum_phase_fraction(&self) -> f64 {
+        match self {
+            SincPhase::Linear => 0.0,
+            SincPhase::Minimum => 1.0,
+            SincPhase::Intermediate(fraction) => (*fraction as f64).clamp(0.0, 1.0),
+        }
+    }
+}
+
 /// Helper function. Make a set of windowed sincs.
 pub fn make_sincs<T>(
     npoints: usize,
     factor: usize,
     f_cutoff: f32,
     windowfunc: WindowFunction,
+    phase: SincPhase,
 ) -> Vec<Vec<T>>
 where
     T: Sample,
@@ -26,15 +58,57 @@ where
     let totpoints = npoints * factor;
     let mut y = Vec::with_capacity(totpoints);
     let window = make_window::<T>(totpoints, windowfunc);
-    let mut sum = T::zero();
     for (x, w) in window.iter().enumerate().take(totpoints) {
         let val = *w
             * sinc(
                 (T::coerce(x) - T::coerce(totpoints / 2)) * T::coerce(f_cutoff) / T::coerce(factor),
             );
-        sum += val;
         y.push(val);
     }
+    let fraction = phase.minimum_phase_fraction();
+    if fraction > 0.0 {
+        let linear: Vec<f64> = y.iter().map(|val| val.to_f64()).collect();
+        y = change_phase(&linear, totpoints / 2, fraction)
+            .iter()
+            .map(|val| T::coerce(*val))
+            .collect();
+    }
+    split_into_sincs(&y, factor)
+}
+
+/// Helper function. Make a set of sincs from a prototype filter.
+///
+/// The prototype is the impulse response of a lowpass filter at the oversampled rate,
+/// in the usual order where the first point is multiplied with the newest sample.
+/// Its length must be a multiple of `factor`, giving a sinc length that is a multiple of 8.
+/// The sincs are normalized to give unity gain at zero frequency.
+pub fn make_sincs_from_prototype<T>(prototype: &[T], factor: usize) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    assert!(
+        factor > 0 && prototype.len() % factor == 0,
+        "Prototype length {} is not a multiple of the oversampling factor {}",
+        prototype.len(),
+        factor
+    );
+    assert!(
+        (prototype.len() / factor) % 8 == 0 && !prototype.is_empty(),
+        "Sinc length must be a multiple of 8"
+    );
+    let y: Vec<T> = prototype.iter().rev().copied().collect();
+    split_into_sincs(&y, factor)
+}
+
+/// Split an oversampled filter into one sinc for each of the intermediate points,
+/// normalized to give unity gain at zero frequency.
+/// The last point of the filter is multiplied with the newest sample.
+fn split_into_sincs<T>(y: &[T], factor: usize) -> Vec<Vec<T>>
+where
+    T: Sample,
+{
+    let npoints = y.len() / factor;
+    let mut sum = y.iter().fold(T::zero(), |acc, val| acc + *val);
     sum /= T::coerce(factor);
     debug!(
         "Generate sincs, length: {}, oversampling: {}, normalized by: {:?}",
@@ -49,17 +123,241 @@ where
     sincs
 }
 
+/// Get the delay of a set of sincs, as made by [make_sincs], in units of input samples.
+///
+/// The delay is given by the center of mass of the impulse response,
+/// which equals the group delay at zero frequency.
+/// It is measured from the last point of the sincs,
+/// since that is the point that is multiplied with the newest sample.
+pub fn sincs_delay<T>(sincs: &[Vec<T>]) -> f64
+where
+    T: Sample,
+{
+    let factor = sincs.len();
+    let npoints = sincs[0].len();
+    let mut sum = 0.0;
+    let mut moment = 0.0;
+    for (subindex, sinc) in sincs.iter().enumerate() {
+        let n = factor - subindex - 1;
+        for (p, val) in sinc.iter().enumerate() {
+            sum += val.to_f64();
+            moment += val.to_f64() * (factor * (npoints - p) - n) as f64;
+        }
+    }
+    moment / sum / factor as f64
+}
+
+/// In-place radix-2 FFT of a buffer of complex values, given as (re, im) pairs.
+/// The length must be a power of two.
+/// The inverse transform is not scaled.
+fn fft(data: &mut [(f64, f64)], inverse: bool) {
+    let len = data.len();
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let twiddles: Vec<(f64, f64)> = (0..len / 2)
+        .map(|k| {
+            let (im, re) = (sign * 2.0 * PI * k as f64 / len as f64).sin_cos();
+            (re, im)
+        })
+        .collect();
+    let mut j = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+    let mut size = 2;
+    while size <= len {
+        let stride = len / size;
+        for start in (0..len).step_by(size) {
+            for k in 0..size / 2 {
+                let (w_re, w_im) = twiddles[k * stride];
+                let (a_re, a_im) = data[start + k];
+                let (b_re, b_im) = data[start + k + size / 2];
+                let (t_re, t_im) = (b_re * w_re - b_im * w_im, b_re * w_im + b_im * w_re);
+                data[start + k] = (a_re + t_re, a_im + t_im);
+                data[start + k + size / 2] = (a_re - t_re, a_im - t_im);
+            }
+        }
+        size <<= 1;
+    }
+}
+
+/// Change the phase of a linear phase impulse response, centered at `center`,
+/// to a mix of linear and minimum phase.
+///
+/// The minimum phase response is found with the cepstral method,
+/// and the phase is interpolated between the linear and minimum phase responses.
+/// The magnitude response is kept unchanged.
+/// The result is reversed, so that the end of the response that comes first in time
+/// is multiplied with the newest sample, like for the linear phase sincs.
+fn change_phase(linear: &[f64], center: usize, fraction: f64) -> Vec<f64> {
+    // Use plenty of zero padding to reduce the time aliasing of the cepstrum.
+    let len = (8 * linear.len()).next_power_of_two();
+    let mut spectrum = vec![(0.0, 0.0); len];
+    for (value, point) in linear.iter().zip(spectrum.iter_mut()) {
+        point.0 = *value;
+    }
+    fft(&mut spectrum, false);
+    let magnitudes: Vec<f64> = spectrum
+        .iter()
+        .map(|(re, im)| (re * re + im * im).sqrt())
+        .collect();
+    // Limit the dynamic range, to avoid taking the logarithm of zero.
+    let floor = 1.0e-12 * magnitudes.iter().fold(0.0, |max: f64, mag| max.max(*mag));
+
+    // Fold the real cepstrum to get the cepstrum of the minimum phase response.
+    let mut cepstrum: Vec<(f64, f64)> = magnitudes
+        .iter()
+        .map(|mag| (mag.max(floor).ln(), 0.0))
+        .collect();
+    fft(&mut cepstrum, true);
+    for (n, value) in cepstrum.iter_mut().enumerate() {
+        let scale = if n == 0 || n == len / 2 {
+            1.0
+        } else if n < len / 2 {
+            2.0
+        } else {
+            0.0
+        };
+        *value = (scale * value.0 / len as f64, 0.0);
+    }
+    // The imaginary part of the transformed cepstrum is the unwrapped minimum phase.
+    fft(&mut cepstrum, false);
+
+    for (k, (point, mag)) in spectrum.iter_mut().zip(magnitudes.iter()).enumerate() {
+        let freq = if k <= len / 2 {
+            k as f64
+        } else {
+            k as f64 - len as f64
+        };
+        let linear_phase = -2.0 * PI * freq * center as f64 / len as f64;
+        let phase = (1.0 - fraction) * linear_phase + fraction * cepstrum[k].1;
+        *point = (mag * phase.cos(), mag * phase.sin());
+    }
+    fft(&mut spectrum, true);
+    spectrum
+        .iter()
+        .take(linear.len())
+        .rev()
+        .map(|(re, _)| re / len as f64)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::sinc::make_sincs;
+    use crate::sinc::{make_sincs, make_sincs_from_prototype, sincs_delay, SincPhase};
     use crate::WindowFunction;
     use test_log::test;
 
     #[test]
     fn sincs() {
-        let sincs = make_sincs::<f64>(32, 8, 0.9, WindowFunction::Blackman);
+        let sincs = make_sincs::<f64>(32, 8, 0.9, WindowFunction::Blackman, SincPhase::Linear);
         assert!((sincs[7][16] - 1.0).abs() < 0.2);
         let sum: f64 = sincs.iter().map(|v| v.iter().sum::<f64>()).sum();
         assert!((sum - 8.0).abs() < 0.00001);
+        assert!((sincs_delay(&sincs) - 16.0).abs() < 0.01);
+    }
+
+    /// Calculate the magnitude response in dB of a set of sincs,
+    /// at the given frequencies relative to the Nyquist frequency.
+    fn magnitude_response(sincs: &[Vec<f64>], freqs: &[f64]) -> Vec<f64> {
+        let factor = sincs.len();
+        freqs
+            .iter()
+            .map(|f| {
+                let mut re = 0.0;
+                let mut im = 0.0;
+                for (n, sinc) in sincs.iter().enumerate() {
+                    for (p, y) in sinc.iter().enumerate() {
+                        let phase = std::f64::consts::PI * f * (factor * p + factor - n) as f64
+                            / factor as f64;
+                        re += y * phase.cos();
+                        im -= y * phase.sin();
+                    }
+                }
+                20.0 * ((re * re + im * im).sqrt() / factor as f64).log10()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sincs_from_prototype() {
+        let sincs = make_sincs::<f64>(32, 8, 0.9, WindowFunction::Blackman, SincPhase::Minimum);
+        // Rebuild the prototype, with the first point in time first.
+        let mut prototype = vec![0.0; 32 * 8];
+        for p in 0..32 {
+            for n in 0..8 {
+                prototype[255 - 8 * p - n] = 3.0 * sincs[7 - n][p];
+            }
+        }
+        let rebuilt = make_sincs_from_prototype(&prototype, 8);
+        for (sinc, sinc_rebuilt) in sincs.iter().zip(rebuilt.iter()) {
+            for (value, value_rebuilt) in sinc.iter().zip(sinc_rebuilt.iter()) {
+                assert!((value - value_rebuilt).abs() < 1.0e-12);
+            }
+        }
+        // The largest value of a minimum phase filter is near the start.
+        let peak = prototype
+            .iter()
+            .enumerate()
+            .fold((0, 0.0), |(peak, max), (n, value)| {
+                if *value > max {
+                    (n, *value)
+                } else {
+                    (peak, max)
+                }
+            });
+        assert!(peak.0 < 32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sincs_from_prototype_wrong_length() {
+        let prototype = vec![0.1; 30 * 8];
+        make_sincs_from_prototype(&prototype, 8);
+    }
+
+    #[test]
+    fn minimum_phase_sincs() {
+        let window = WindowFunction::BlackmanHarris2;
+        let linear = make_sincs::<f64>(64, 16, 0.9, window, SincPhase::Linear);
+        let minimum = make_sincs::<f64>(64, 16, 0.9, window, SincPhase::Minimum);
+        let sum: f64 = minimum.iter().map(|v| v.iter().sum::<f64>()).sum();
+        assert!((sum - 16.0).abs() < 0.00001);
+
+        // The magnitude response is unchanged.
+        let freqs = [0.1, 0.5, 0.8, 1.2, 1.5];
+        let response_linear = magnitude_response(&linear, &freqs);
+        let response_minimum = magnitude_response(&minimum, &freqs);
+        for (lin, min) in response_linear.iter().zip(response_minimum.iter()) {
+            if *lin > -3.0 {
+                assert!((lin - min).abs() < 0.001, "{} {}", lin, min);
+            } else {
+                assert!(*min < -100.0, "{} {}", lin, min);
+            }
+        }
+
+        // The delay is much shorter, and for the intermediate phase it is in between.
+        let delay_minimum = sincs_delay(&minimum);
+        assert!(
+            delay_minimum > 0.0 && delay_minimum < 8.0,
+            "{}",
+            delay_minimum
+        );
+        let intermediate = make_sincs::<f64>(64, 16, 0.9, window, SincPhase::Intermediate(0.5));
+        let delay_intermediate = sincs_delay(&intermediate);
+        let expected = 0.5 * (delay_minimum + sincs_delay(&linear));
+        assert!(
+            (delay_intermediate - expected).abs() < 0.1,
+            "{} {}",
+            delay_intermediate,
+            expected
+        );
     }
 }
@@ -1,4 +1,4 @@
-use crate::sinc::make_sincs;
+use crate::sinc::{make_sincs, make_sincs_from_prototype, sincs_delay, SincPhase};
 use crate::windows::WindowFunction;
 use crate::Sample;
 
@@ -49,7 +49,7 @@ interpolator! {
 }
 
 /// Functions for making the scalar product with a sinc.
-pub trait SincInterpolator<T>: Send {
+pub trait SincInterpolator<T>: Send + Sync {
     /// Make the scalar product between the waveform starting at `index` and the sinc of `subindex`.
     fn get_sinc_interpolated(&self, wave: &[T], index: usize, subindex: usize) -> T;
 
@@ -63,6 +63,13 @@ pub trait SincInterpolator<T>: Send {
 
     /// Get number of sincs used for oversampling.
     fn nbr_sincs(&self) -> usize;
+
+    /// Get the delay of the sincs in units of input samples,
+    /// measured from the last point of the sinc.
+    /// This is half the sinc length for linear phase sincs, and shorter for minimum phase.
+    fn delay(&self) -> f64 {
+        self.len() as f64 / 2.0
+    }
 }
 
 /// A plain scalar interpolator.
@@ -70,6 +77,7 @@ pub struct ScalarInterpolator<T> {
     sincs: Vec<Vec<T>>,
     length: usize,
     nbr_sincs: usize,
+    delay: f64,
 }
 
 impl<T> SincInterpolator<T> for ScalarInterpolator<T>
@@ -124,6 +132,10 @@ where
     fn nbr_sincs(&self) -> usize {
         self.nbr_sincs
     }
+
+    fn delay(&self) -> f64 {
+        self.delay
+    }
 }
 
 impl<T> ScalarInterpolator<T>
@@ -137,18 +149,40 @@ where
     /// - `oversampling_factor`: Number of intermediate sincs (oversampling factor).
     /// - `f_cutoff`: Relative cutoff frequency.
     /// - `window`: Window function to use.
+    /// - `phase`: Phase response of the sincs.
     pub fn new(
         sinc_len: usize,
         oversampling_factor: usize,
         f_cutoff: f32,
         window: WindowFunction,
+        phase: SincPhase,
     ) -> Self {
         assert!(sinc_len % 8 == 0, "Sinc length must be a multiple of 8");
-        let sincs = make_sincs(sinc_len, oversampling_factor, f_cutoff, window);
+        let sincs = make_sincs(sinc_len, oversampling_factor, f_cutoff, window, phase);
+        Self::from_sincs(sincs)
+    }
+
+    /// Create a new ScalarInterpolator from a prototype filter.
+    ///
+    /// Parameters are:
+    /// - `prototype`: Impulse response of a lowpass filter at the oversampled rate,
+    ///   with a length of sinc length times oversampling factor.
+    ///   The sinc length must be a multiple of 8.
+    ///   The first point is multiplied with the newest sample.
+    ///   The filter is normalized to give unity gain at zero frequency.
+    /// - `oversampling_factor`: Number of intermediate sincs (oversampling factor).
+    pub fn new_with_prototype(prototype: &[T], oversampling_factor: usize) -> Self {
+        let sincs = make_sincs_from_prototype(prototype, oversampling_factor);
+        Self::from_sincs(sincs)
+    }
+
+    fn from_sincs(sincs: Vec<Vec<T>>) -> Self {
+        let delay = sincs_delay(&sincs);
         Self {
+            length: sincs[0].len(),
+            nbr_sincs: sincs.len(),
             sincs,
-            length: sinc_len,
-            nbr_sincs: oversampling_factor,
+            delay,
         }
     }
 }
@@ -157,7 +191,7 @@ where
 mod tests {
     use super::ScalarInterpolator;
     use super::SincInterpolator;
-    use crate::WindowFunction;
+    use crate::{SincPhase, WindowFunction};
     use num_traits::Float;
     use rand::Rng;
     use test_log::test;
@@ -178,12 +212,17 @@ mod tests {
             wave.push(rng.gen::<f64>());
         }
         let sinc_len = 256;
-        let f_cutoff = 0.9473371669037001;
+        let f_cutoff = 0.947_337_2;
         let oversampling_factor = 256;
         let window = WindowFunction::BlackmanHarris2;
 
-        let interpolator =
-            ScalarInterpolator::<f64>::new(sinc_len, oversampling_factor, f_cutoff, window);
+        let interpolator = ScalarInterpolator::<f64>::new(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            window,
+            SincPhase::Linear,
+        );
         let value = interpolator.get_sinc_interpolated(&wave, 333, 123);
         let check = get_sinc_interpolated(&wave, 333, &interpolator.sincs[123]);
         assert!((value - check).abs() < 1.0e-9);
@@ -197,12 +236,17 @@ mod tests {
             wave.push(rng.gen::<f32>());
         }
         let sinc_len = 256;
-        let f_cutoff = 0.9473371669037001;
+        let f_cutoff = 0.947_337_2;
         let oversampling_factor = 256;
         let window = WindowFunction::BlackmanHarris2;
 
-        let interpolator =
-            ScalarInterpolator::<f32>::new(sinc_len, oversampling_factor, f_cutoff, window);
+        let interpolator = ScalarInterpolator::<f32>::new(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            window,
+            SincPhase::Linear,
+        );
         let value = interpolator.get_sinc_interpolated(&wave, 333, 123);
         let check = get_sinc_interpolated(&wave, 333, &interpolator.sincs[123]);
         assert!((value - check).abs() < 1.0e-6);
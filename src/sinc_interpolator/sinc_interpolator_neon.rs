@@ -1,5 +1,5 @@
 use crate::error::{CpuFeature, MissingCpuFeature};
-use crate::sinc::make_sincs;
+use crate::sinc::{make_sincs, make_sincs_from_prototype, sincs_delay, SincPhase};
 use crate::sinc_interpolator::SincInterpolator;
 use crate::windows::WindowFunction;
 use crate::Sample;
@@ -13,8 +13,8 @@ use core::arch::aarch64::{vaddq_f64, vfmaq_f64, vld1q_f64, vmovq_n_f64, vst1q_f6
 static FEATURES: &[CpuFeature] = &[CpuFeature::Neon];
 
 /// Trait governing what can be done with an NeonSample.
-pub trait NeonSample: Sized + Send {
-    type Sinc: Send;
+pub trait NeonSample: Sized + Send + Sync {
+    type Sinc: Send + Sync;
 
     /// Pack sincs into a vector.
     ///
@@ -150,6 +150,7 @@ where
     sincs: Vec<Vec<T::Sinc>>,
     length: usize,
     nbr_sincs: usize,
+    delay: f64,
 }
 
 impl<T> SincInterpolator<T> for NeonInterpolator<T>
@@ -180,6 +181,10 @@ where
     fn nbr_sincs(&self) -> usize {
         self.nbr_sincs
     }
+
+    fn delay(&self) -> f64 {
+        self.delay
+    }
 }
 
 impl<T> NeonInterpolator<T>
@@ -193,36 +198,67 @@ where
     /// - `oversampling_factor`: Number of intermediate sincs (oversampling factor).
     /// - `f_cutoff`: Relative cutoff frequency.
     /// - `window`: Window function to use.
+    /// - `phase`: Phase response of the sincs.
     pub fn new(
         sinc_len: usize,
         oversampling_factor: usize,
         f_cutoff: f32,
         window: WindowFunction,
+        phase: SincPhase,
     ) -> Result<Self, MissingCpuFeature> {
         if let Some(feature) = FEATURES.iter().find(|f| !f.is_detected()) {
             return Err(MissingCpuFeature(*feature));
         }
 
         assert!(sinc_len % 8 == 0, "Sinc length must be a multiple of 8.");
-        let sincs = make_sincs(sinc_len, oversampling_factor, f_cutoff, window);
-        let sincs = unsafe { <T as NeonSample>::pack_sincs(sincs) };
+        let sincs = make_sincs(sinc_len, oversampling_factor, f_cutoff, window, phase);
+        Ok(Self::from_sincs(sincs))
+    }
+
+    /// Create a new NeonInterpolator from a prototype filter.
+    ///
+    /// Parameters are:
+    /// - `prototype`: Impulse response of a lowpass filter at the oversampled rate,
+    ///   with a length of sinc length times oversampling factor.
+    ///   The sinc length must be a multiple of 8.
+    ///   The first point is multiplied with the newest sample.
+    ///   The filter is normalized to give unity gain at zero frequency.
+    /// - `oversampling_factor`: Number of intermediate sincs (oversampling factor).
+    pub fn new_with_prototype(
+        prototype: &[T],
+        oversampling_factor: usize,
+    ) -> Result<Self, MissingCpuFeature> {
+        if let Some(feature) = FEATURES.iter().find(|f| !f.is_detected()) {
+            return Err(MissingCpuFeature(*feature));
+        }
+
+        let sincs = make_sincs_from_prototype(prototype, oversampling_factor);
+        Ok(Self::from_sincs(sincs))
+    }
 
-        Ok(Self {
+    fn from_sincs(sincs: Vec<Vec<T>>) -> Self {
+        let length = sincs[0].len();
+        let nbr_sincs = sincs.len();
+        let delay = sincs_delay(&sincs);
+        let sincs = unsafe { <T as NeonSample>::pack_sincs(sincs) };
+        Self {
             sincs,
-            length: sinc_len,
-            nbr_sincs: oversampling_factor,
-        })
+            length,
+            nbr_sincs,
+            delay,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::sinc::make_sincs;
+    use crate::sinc::{make_sincs, SincPhase};
     use crate::sinc_interpolator::sinc_interpolator_neon::NeonInterpolator;
-    use crate::sinc_interpolator::SincInterpolator;
+    use crate::sinc_interpolator::{ScalarInterpolator, SincInterpolator};
     use crate::WindowFunction;
     use num_traits::Float;
     use rand::Rng;
+    use std::f64::consts::PI;
     use test_log::test;
 
     fn get_sinc_interpolated<T: Float>(wave: &[T], index: usize, sinc: &[T]) -> T {
@@ -233,6 +269,23 @@ mod tests {
             .fold(T::zero(), |acc, (x, y)| acc + *x * *y)
     }
 
+    /// Windowed sinc prototype with a Hann window, for comparing with the scalar interpolator.
+    fn make_prototype(sinc_len: usize, oversampling_factor: usize) -> Vec<f64> {
+        let length = sinc_len * oversampling_factor;
+        (0..length)
+            .map(|n| {
+                let x = (n as f64 - (length / 2) as f64) / oversampling_factor as f64;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (0.9 * PI * x).sin() / (0.9 * PI * x)
+                };
+                let window = 0.5 - 0.5 * (2.0 * PI * n as f64 / length as f64).cos();
+                sinc * window
+            })
+            .collect()
+    }
+
     #[test]
     fn test_neon_interpolator_64() {
         let mut rng = rand::thread_rng();
@@ -241,12 +294,24 @@ mod tests {
             wave.push(rng.gen::<f64>());
         }
         let sinc_len = 256;
-        let f_cutoff = 0.9473371669037001;
+        let f_cutoff = 0.947_337_2;
         let oversampling_factor = 256;
         let window = WindowFunction::BlackmanHarris2;
-        let sincs = make_sincs::<f64>(sinc_len, oversampling_factor, f_cutoff, window);
-        let interpolator =
-            NeonInterpolator::<f64>::new(sinc_len, oversampling_factor, f_cutoff, window).unwrap();
+        let sincs = make_sincs::<f64>(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            window,
+            SincPhase::Linear,
+        );
+        let interpolator = NeonInterpolator::<f64>::new(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            window,
+            SincPhase::Linear,
+        )
+        .unwrap();
         let value = interpolator.get_sinc_interpolated(&wave, 333, 123);
         let check = get_sinc_interpolated(&wave, 333, &sincs[123]);
         assert!((value - check).abs() < 1.0e-9);
@@ -260,14 +325,67 @@ mod tests {
             wave.push(rng.gen::<f32>());
         }
         let sinc_len = 256;
-        let f_cutoff = 0.9473371669037001;
+        let f_cutoff = 0.947_337_2;
         let oversampling_factor = 256;
         let window = WindowFunction::BlackmanHarris2;
-        let sincs = make_sincs::<f32>(sinc_len, oversampling_factor, f_cutoff, window);
-        let interpolator =
-            NeonInterpolator::<f32>::new(sinc_len, oversampling_factor, f_cutoff, window).unwrap();
+        let sincs = make_sincs::<f32>(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            window,
+            SincPhase::Linear,
+        );
+        let interpolator = NeonInterpolator::<f32>::new(
+            sinc_len,
+            oversampling_factor,
+            f_cutoff,
+            window,
+            SincPhase::Linear,
+        )
+        .unwrap();
         let value = interpolator.get_sinc_interpolated(&wave, 333, 123);
         let check = get_sinc_interpolated(&wave, 333, &sincs[123]);
         assert!((value - check).abs() < 1.0e-5);
     }
+
+    #[test]
+    fn test_neon_interpolator_prototype_64() {
+        let mut rng = rand::thread_rng();
+        let mut wave = Vec::new();
+        for _ in 0..2048 {
+            wave.push(rng.gen::<f64>());
+        }
+        let oversampling_factor = 16;
+        let prototype = make_prototype(64, oversampling_factor);
+        let scalar = ScalarInterpolator::<f64>::new_with_prototype(&prototype, oversampling_factor);
+        let interpolator =
+            NeonInterpolator::<f64>::new_with_prototype(&prototype, oversampling_factor).unwrap();
+        for subindex in [0, 7, 15] {
+            let value = interpolator.get_sinc_interpolated(&wave, 333, subindex);
+            let check = scalar.get_sinc_interpolated(&wave, 333, subindex);
+            assert!((value - check).abs() < 1.0e-9);
+        }
+    }
+
+    #[test]
+    fn test_neon_interpolator_prototype_32() {
+        let mut rng = rand::thread_rng();
+        let mut wave = Vec::new();
+        for _ in 0..2048 {
+            wave.push(rng.gen::<f32>());
+        }
+        let oversampling_factor = 16;
+        let prototype = make_prototype(64, oversampling_factor)
+            .iter()
+            .map(|v| *v as f32)
+            .collect::<Vec<f32>>();
+        let scalar = ScalarInterpolator::<f32>::new_with_prototype(&prototype, oversampling_factor);
+        let interpolator =
+            NeonInterpolator::<f32>::new_with_prototype(&prototype, oversampling_factor).unwrap();
+        for subindex in [0, 7, 15] {
+            let value = interpolator.get_sinc_interpolated(&wave, 333, subindex);
+            let check = scalar.get_sinc_interpolated(&wave, 333, subindex);
+            assert!((value - check).abs() < 1.0e-5);
+        }
+    }
 }
@@ -0,0 +1,132 @@
+use crate::error::{ResampleError, ResampleResult};
+
+/// A snapshot of the internal state of an asynchronous resampler,
+/// [SincFixedIn](crate::SincFixedIn), [SincFixedOut](crate::SincFixedOut),
+/// [FastFixedIn](crate::FastFixedIn) or [FastFixedOut](crate::FastFixedOut).
+///
+/// The snapshot is taken with the `state()` method of the resampler,
+/// and is restored with `set_state()` on a resampler that was created with the same parameters.
+/// After restoring, the resampler continues exactly where the original left off,
+/// and gives bit-identical output.
+///
+/// With the `serde` feature enabled, the snapshot can be serialized and deserialized with serde.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AsyncResamplerState<T> {
+    /// The current chunk size.
+    pub chunk_size: usize,
+    /// The position of the next output frame, relative to the start of the buffer.
+    pub last_index: f64,
+    /// The current resampling ratio.
+    pub resample_ratio: f64,
+    /// The target resampling ratio, differs from `resample_ratio` while ramping.
+    pub target_ratio: f64,
+    /// The number of input frames needed for the next chunk.
+    /// Only used by the fixed output resamplers, zero for the others.
+    pub needed_input_size: usize,
+    /// The number of valid frames in the buffer.
+    /// Only used by the fixed output resamplers, zero for the others.
+    pub buffer_fill: usize,
+    /// The contents of the internal buffer, one vector per channel.
+    pub buffer: Vec<Vec<T>>,
+    /// The number of output frames corresponding to the input frames processed so far,
+    /// used for calculating the number of frames to output when flushing.
+    pub output_frames_expected: f64,
+    /// The number of output frames produced so far.
+    pub output_frames_produced: usize,
+}
+
+/// A snapshot of the internal state of a synchronous resampler,
+/// [FftFixedIn](crate::FftFixedIn), [FftFixedOut](crate::FftFixedOut)
+/// or [FftFixedInOut](crate::FftFixedInOut).
+///
+/// The snapshot is taken with the `state()` method of the resampler,
+/// and is restored with `set_state()` on a resampler that was created with the same parameters.
+/// After restoring, the resampler continues exactly where the original left off,
+/// and gives bit-identical output.
+///
+/// With the `serde` feature enabled, the snapshot can be serialized and deserialized with serde.
+#[cfg(feature = "fft_resampler")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FftResamplerState<T> {
+    /// The overlapping output frames that are added to the next processed block.
+    pub overlaps: Vec<Vec<T>>,
+    /// The contents of the input buffer of [FftFixedIn](crate::FftFixedIn),
+    /// or the output buffer of [FftFixedOut](crate::FftFixedOut).
+    /// Empty for [FftFixedInOut](crate::FftFixedInOut).
+    pub buffers: Vec<Vec<T>>,
+    /// The number of frames stored in `buffers`.
+    pub saved_frames: usize,
+    /// The current chunk size.
+    pub chunk_size: usize,
+    /// The number of output frames corresponding to the input frames processed so far,
+    /// used for calculating the number of frames to output when flushing.
+    pub output_frames_expected: f64,
+    /// The number of output frames produced so far.
+    pub output_frames_produced: usize,
+}
+
+/// Check that a buffer in a state snapshot has the same shape as the buffer of the resampler.
+pub(crate) fn validate_state_buffer<T>(
+    resampler_buffer: &[Vec<T>],
+    state_buffer: &[Vec<T>],
+) -> ResampleResult<()> {
+    if resampler_buffer.len() != state_buffer.len()
+        || resampler_buffer
+            .iter()
+            .zip(state_buffer.iter())
+            .any(|(a, b)| a.len() != b.len())
+    {
+        return Err(ResampleError::IncompatibleState);
+    }
+    Ok(())
+}
+
+/// Check that the index in a state snapshot is a finite number within `min..=max`.
+pub(crate) fn validate_state_index(index: f64, min: f64, max: f64) -> ResampleResult<()> {
+    if index.is_finite() && index >= min && index <= max {
+        Ok(())
+    } else {
+        Err(ResampleError::IncompatibleState)
+    }
+}
+
+/// Check that the expected number of output frames in a state snapshot is a finite number.
+pub(crate) fn validate_state_frames(expected: f64) -> ResampleResult<()> {
+    if expected.is_finite() {
+        Ok(())
+    } else {
+        Err(ResampleError::IncompatibleState)
+    }
+}
+
+/// Get the first step of the interpolation index, when the step changes linearly
+/// from `1/resample_ratio` to `1/target_ratio` over `steps` steps.
+pub(crate) fn first_index_step(resample_ratio: f64, target_ratio: f64, steps: f64) -> f64 {
+    1.0 / resample_ratio + (1.0 / target_ratio - 1.0 / resample_ratio) / steps
+}
+
+/// Get the sum of `steps` steps of the interpolation index,
+/// when the step changes linearly from `1/resample_ratio` to `1/target_ratio`.
+pub(crate) fn total_index_step(resample_ratio: f64, target_ratio: f64, steps: usize) -> f64 {
+    let steps = steps as f64;
+    steps / resample_ratio + (1.0 / target_ratio - 1.0 / resample_ratio) * (steps + 1.0) / 2.0
+}
+
+/// Check that a ratio in a state snapshot is within the allowed range of the resampler.
+pub(crate) fn validate_state_ratio(
+    ratio: f64,
+    original: f64,
+    max_relative_ratio: f64,
+) -> ResampleResult<()> {
+    if ratio / original >= 1.0 / max_relative_ratio && ratio / original <= max_relative_ratio {
+        Ok(())
+    } else {
+        Err(ResampleError::RatioOutOfBounds {
+            provided: ratio,
+            original,
+            max_relative_ratio,
+        })
+    }
+}
@@ -1,5 +1,7 @@
+use crate::adapters::{AudioInput, AudioOutput, ZeroPadded};
 use crate::error::ResamplerConstructionError;
-use crate::sinc::make_sincs;
+use crate::sinc::{make_sincs, SincPhase};
+use crate::state::{validate_state_buffer, validate_state_frames, FftResamplerState};
 use crate::windows::WindowFunction;
 use num_complex::Complex;
 use num_integer as integer;
@@ -7,14 +9,71 @@ use num_traits::Zero;
 use std::sync::Arc;
 
 use crate::error::{ResampleError, ResampleResult};
-use crate::{calculate_cutoff, update_mask_from_buffers, validate_buffers, Resampler, Sample};
+use crate::{
+    calculate_cutoff, flush_resampler, partial_input_frames, update_mask_from_buffers,
+    validate_buffers, validate_sample_rates, FrameCounter, Resampler, Sample,
+};
 use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
 
+/// A struct holding the parameters for the antialiasing filter of the FFT resamplers.
+#[derive(Debug, Clone, Copy)]
+pub struct FftFilterParameters {
+    /// Minimum length of the windowed sinc filter, in input frames.
+    /// The value will be rounded up to a multiple of the FFT input size,
+    /// and the delay of the resampler is half the resulting length.
+    /// A longer filter gives a steeper cutoff at the expense of higher cpu usage.
+    /// A filter longer than the FFT input size is split into partitions of that size,
+    /// and each new unit is transformed once and multiplied with every partition.
+    /// The processing per frame therefore grows about in proportion
+    /// to the number of partitions.
+    pub filter_len: usize,
+    /// Relative cutoff frequency of the filter
+    /// (relative to the lowest one of fs_in/2 or fs_out/2).
+    /// Use `None` to calculate a suitable value with [calculate_cutoff](crate::calculate_cutoff),
+    /// for the actual filter length and the selected window function.
+    /// A given value must be > 0 and <= 1,
+    /// otherwise [ResamplerConstructionError::InvalidCutoff] is returned.
+    pub f_cutoff: Option<f32>,
+    /// Window function to use.
+    pub window: WindowFunction,
+}
+
+impl Default for FftFilterParameters {
+    /// The default filter is as long as the FFT, with a Blackman-Harris2 window
+    /// and a cutoff calculated with [calculate_cutoff](crate::calculate_cutoff).
+    /// This is the filter used by the `new()` constructors.
+    fn default() -> Self {
+        FftFilterParameters {
+            filter_len: 1,
+            f_cutoff: None,
+            window: WindowFunction::BlackmanHarris2,
+        }
+    }
+}
+
 /// A helper for resampling a single chunk of data.
+///
+/// Each chunk is zero padded to twice its length and transformed.
+/// When the antialiasing filter is as long as the chunk, the spectrum is multiplied with the filter,
+/// and is then extended or truncated to the output length before it's inverse transformed.
+///
+/// A longer filter is split into `filter_units` partitions as long as the chunk,
+/// and is applied with a uniformly partitioned overlap-add convolution.
+/// The spectra of the previous chunks are kept in a frequency domain delay line for each channel,
+/// and each new chunk needs one transform, one product per partition and one inverse transform.
+/// The partitions are not band limited, so their spectra can't simply be truncated or extended
+/// to change the sample rate. Instead they are transformed at the least common multiple
+/// of the input and output sample rates, as in a polyphase resampler,
+/// and the products are folded down to the output rate.
+#[derive(Clone)]
 struct FftResampler<T> {
     fft_size_in: usize,
     fft_size_out: usize,
-    filter_f: Vec<Complex<T>>,
+    filter_units: usize,
+    // Shared between clones. Stored as a trait object, like the sinc interpolators,
+    // so that the resampler is `Send` whenever `T` is.
+    // Holds the spectrum of the filter, or the folded spectra of the partitions.
+    filter_f: Arc<dyn AsRef<[Complex<T>]> + Send + Sync>,
     fft: Arc<dyn RealToComplex<T>>,
     ifft: Arc<dyn ComplexToReal<T>>,
     scratch_fw: Vec<Complex<T>>,
@@ -23,40 +82,68 @@ struct FftResampler<T> {
     input_f: Vec<Complex<T>>,
     output_f: Vec<Complex<T>>,
     output_buf: Vec<T>,
+    // The frequency domain delay lines of a partitioned filter, one per channel.
+    spectra: Vec<Vec<Complex<T>>>,
+    // The position of the newest spectrum in each delay line.
+    newest: Vec<usize>,
 }
 
 /// A synchronous resampler that needs a fixed number of audio frames for input
 /// and returns a variable number of frames.
+/// The number of input frames is determined by the chunk size argument to the constructor.
+/// This value can be changed by the `set_chunk_size()` method,
+/// to let the resampler process smaller chunks of audio data.
+/// Note that the chunk size cannot exceed the value given at creation time.
 ///
 /// The resampling is done by FFT:ing the input data. The spectrum is then extended or
 /// truncated as well as multiplied with an antialiasing filter
 /// before it's inverse transformed to get the resampled waveforms.
+///
+/// By default the antialiasing filter is as long as the FFT.
+/// A longer filter can be used by creating the resampler with `new_with_filter_length()`,
+/// which is useful for getting a steep filter with a small chunk size.
+/// The window, cutoff and length of the filter can all be chosen with `new_with_filter()`.
+#[derive(Clone)]
 pub struct FftFixedIn<T> {
     nbr_channels: usize,
     chunk_size_in: usize,
+    max_chunk_size_in: usize,
     fft_size_in: usize,
     fft_size_out: usize,
     overlaps: Vec<Vec<T>>,
     input_buffers: Vec<Vec<T>>,
     channel_mask: Vec<bool>,
+    frame_counter: FrameCounter,
     saved_frames: usize,
     resampler: FftResampler<T>,
 }
 
 /// A synchronous resampler that needs a varying number of audio frames for input
 /// and returns a fixed number of frames.
+/// The number of output frames is determined by the chunk size argument to the constructor.
+/// This value can be changed by the `set_chunk_size()` method,
+/// to let the resampler process smaller chunks of audio data.
+/// Note that the chunk size cannot exceed the value given at creation time.
 ///
 /// The resampling is done by FFT:ing the input data. The spectrum is then extended or
 /// truncated as well as multiplied with an antialiasing filter
 /// before it's inverse transformed to get the resampled waveforms.
+///
+/// By default the antialiasing filter is as long as the FFT.
+/// A longer filter can be used by creating the resampler with `new_with_filter_length()`,
+/// which is useful for getting a steep filter with a small chunk size.
+/// The window, cutoff and length of the filter can all be chosen with `new_with_filter()`.
+#[derive(Clone)]
 pub struct FftFixedOut<T> {
     nbr_channels: usize,
     chunk_size_out: usize,
+    max_chunk_size_out: usize,
     fft_size_in: usize,
     fft_size_out: usize,
     overlaps: Vec<Vec<T>>,
     output_buffers: Vec<Vec<T>>,
     channel_mask: Vec<bool>,
+    frame_counter: FrameCounter,
     saved_frames: usize,
     frames_needed: usize,
     resampler: FftResampler<T>,
@@ -68,47 +155,51 @@ pub struct FftFixedOut<T> {
 /// The resampling is done by FFT:ing the input data. The spectrum is then extended or
 /// truncated as well as multiplied with an antialiasing filter
 /// before it's inverse transformed to get the resampled waveforms.
+///
+/// By default the antialiasing filter is as long as the FFT.
+/// A longer filter can be used by creating the resampler with `new_with_filter_length()`,
+/// which is useful for getting a steep filter with a small chunk size.
+/// The window, cutoff and length of the filter can all be chosen with `new_with_filter()`.
+#[derive(Clone)]
 pub struct FftFixedInOut<T> {
     nbr_channels: usize,
     chunk_size_in: usize,
     chunk_size_out: usize,
     fft_size_in: usize,
     channel_mask: Vec<bool>,
+    frame_counter: FrameCounter,
     overlaps: Vec<Vec<T>>,
     resampler: FftResampler<T>,
 }
 
-fn validate_sample_rates(input: usize, output: usize) -> Result<(), ResamplerConstructionError> {
-    if input == 0 || output == 0 {
-        return Err(ResamplerConstructionError::InvalidSampleRate { input, output });
-    }
-    Ok(())
-}
-
 impl<T> FftResampler<T>
 where
     T: Sample,
 {
-    //
-    pub fn new(fft_size_in: usize, fft_size_out: usize) -> Self {
+    /// Create a new FftResampler, with a filter of `filter_units * fft_size_in` frames.
+    /// The cutoff is relative to the lowest Nyquist frequency, and is calculated
+    /// from the filter length and window if not given.
+    pub fn new(
+        fft_size_in: usize,
+        fft_size_out: usize,
+        filter_units: usize,
+        f_cutoff: Option<f32>,
+        window: WindowFunction,
+        nbr_channels: usize,
+    ) -> Self {
+        let filter_len = filter_units * fft_size_in;
         // calculate antialiasing cutoff
         let cutoff = if fft_size_in > fft_size_out {
-            calculate_cutoff::<f32>(fft_size_out, WindowFunction::BlackmanHarris2)
+            f_cutoff.unwrap_or_else(|| calculate_cutoff(filter_units * fft_size_out, window))
                 * fft_size_out as f32
                 / fft_size_in as f32
         } else {
-            calculate_cutoff::<f32>(fft_size_in, WindowFunction::BlackmanHarris2)
+            f_cutoff.unwrap_or_else(|| calculate_cutoff(filter_len, window))
         };
         debug!(
-            "Create new FftResampler, fft_size_in: {}, fft_size_out: {}, cutoff: {}",
-            fft_size_in, fft_size_out, cutoff
+            "Create new FftResampler, fft_size_in: {}, fft_size_out: {}, filter_len: {}, cutoff: {}",
+            fft_size_in, fft_size_out, filter_len, cutoff
         );
-        let sinc = make_sincs::<T>(fft_size_in, 1, cutoff, WindowFunction::BlackmanHarris2);
-        let mut filter_t: Vec<T> = vec![T::zero(); 2 * fft_size_in];
-        let mut filter_f: Vec<Complex<T>> = vec![Complex::zero(); fft_size_in + 1];
-        for (n, f) in filter_t.iter_mut().enumerate().take(fft_size_in) {
-            *f = sinc[0][n] / T::coerce(2 * fft_size_in);
-        }
 
         let input_f: Vec<Complex<T>> = vec![Complex::zero(); fft_size_in + 1];
         let input_buf: Vec<T> = vec![T::zero(); 2 * fft_size_in];
@@ -117,14 +208,36 @@ where
         let mut planner = RealFftPlanner::<T>::new();
         let fft = planner.plan_fft_forward(2 * fft_size_in);
         let ifft = planner.plan_fft_inverse(2 * fft_size_out);
-        fft.process(&mut filter_t, &mut filter_f).unwrap();
         let scratch_fw = fft.make_scratch_vec();
         let scratch_inv = ifft.make_scratch_vec();
 
+        let (filter_f, spectra) = if filter_units == 1 {
+            let sinc = make_sincs::<T>(filter_len, 1, cutoff, window, SincPhase::Linear);
+            let mut filter_t: Vec<T> = vec![T::zero(); 2 * fft_size_in];
+            let mut filter_f: Vec<Complex<T>> = vec![Complex::zero(); fft_size_in + 1];
+            for (n, f) in filter_t.iter_mut().enumerate().take(filter_len) {
+                *f = sinc[0][n] / T::coerce(2 * fft_size_in);
+            }
+            fft.process(&mut filter_t, &mut filter_f).unwrap();
+            (filter_f, Vec::new())
+        } else {
+            let filter_f = partition_spectra(
+                fft_size_in,
+                fft_size_out,
+                filter_units,
+                cutoff,
+                window,
+                &mut planner,
+            );
+            let spectra = vec![vec![Complex::zero(); 2 * fft_size_in * filter_units]; nbr_channels];
+            (filter_f, spectra)
+        };
+
         FftResampler {
             fft_size_in,
             fft_size_out,
-            filter_f,
+            filter_units,
+            filter_f: Arc::new(filter_f),
             fft,
             ifft,
             scratch_fw,
@@ -133,45 +246,75 @@ where
             input_f,
             output_f,
             output_buf,
+            spectra,
+            newest: vec![0; nbr_channels],
         }
     }
 
-    /// Resample a small chunk.
-    fn resample_unit(&mut self, wave_in: &[T], wave_out: &mut [T], overlap: &mut [T]) {
-        // Copy to input buffer and clear padding area.
-        self.input_buf[0..self.fft_size_in].copy_from_slice(wave_in);
-        for item in self
-            .input_buf
-            .iter_mut()
-            .skip(self.fft_size_in)
-            .take(self.fft_size_in)
-        {
+    /// The length of the overlap buffer needed for each channel.
+    /// For a partitioned filter, the overlapping output frames are followed by
+    /// the input frames of the previous chunks, that are stored as spectra in the delay line.
+    fn overlap_len(&self) -> usize {
+        self.fft_size_out + (self.filter_units - 1) * self.fft_size_in
+    }
+
+    /// The delay of the filter, in output frames.
+    fn output_delay(&self) -> usize {
+        self.filter_units * self.fft_size_out / 2
+    }
+
+    /// Get the part of the input buffer that should be filled with
+    /// the next small chunk before calling [resample_unit](FftResampler::resample_unit).
+    fn input_unit(&mut self) -> &mut [T] {
+        &mut self.input_buf[0..self.fft_size_in]
+    }
+
+    /// Resample the small chunk stored in the input buffer,
+    /// and return a slice with the resampled frames.
+    fn resample_unit(&mut self, channel: usize, overlap: &mut [T]) -> &[T] {
+        // Clear padding area of input buffer.
+        for item in self.input_buf.iter_mut().skip(self.fft_size_in) {
             *item = T::zero();
         }
+        if self.filter_units > 1 {
+            // Keep the input frames, for recreating the delay line after a reset or restore.
+            let history = &mut overlap[self.fft_size_out..];
+            history.copy_within(self.fft_size_in.., 0);
+            let start = history.len() - self.fft_size_in;
+            history[start..].copy_from_slice(&self.input_buf[0..self.fft_size_in]);
+        }
 
         // FFT and store result in history, update index.
         self.fft
             .process_with_scratch(&mut self.input_buf, &mut self.input_f, &mut self.scratch_fw)
             .unwrap();
 
-        let new_len = if self.fft_size_in < self.fft_size_out {
-            self.fft_size_in + 1
+        if self.filter_units > 1 {
+            let slot = (self.newest[channel] + 1) % self.filter_units;
+            self.store_spectrum(channel, slot);
+            self.newest[channel] = slot;
+            self.accumulate_partitions(channel);
         } else {
-            self.fft_size_out
-        };
-
-        // Multiply with filter FT.
-        self.input_f
-            .iter_mut()
-            .take(new_len)
-            .zip(self.filter_f.iter())
-            .for_each(|(spec, filt)| *spec *= filt);
-
-        // copy to modified spectrum
-        self.output_f[0..new_len].copy_from_slice(&self.input_f[0..new_len]);
-        for val in self.output_f[new_len..].iter_mut() {
-            *val = Complex::zero();
+            let new_len = if self.fft_size_in < self.fft_size_out {
+                self.input_f.len()
+            } else {
+                self.fft_size_out
+            };
+
+            // Multiply with filter FT.
+            self.input_f
+                .iter_mut()
+                .take(new_len)
+                .zip(self.filter_f.as_ref().as_ref().iter())
+                .for_each(|(spec, filt)| *spec *= filt);
+
+            // copy to modified spectrum
+            self.output_f[0..new_len].copy_from_slice(&self.input_f[0..new_len]);
+            for val in self.output_f[new_len..].iter_mut() {
+                *val = Complex::zero();
+            }
         }
+
         // IFFT result, store result and overlap.
         self.ifft
             .process_with_scratch(
@@ -180,11 +323,241 @@ where
                 &mut self.scratch_inv,
             )
             .unwrap();
-        for (n, item) in wave_out.iter_mut().enumerate().take(self.fft_size_out) {
-            *item = self.output_buf[n] + overlap[n];
+        let overlap = &mut overlap[0..self.fft_size_out];
+        for (item, overlap_item) in self.output_buf.iter_mut().zip(overlap.iter()) {
+            *item += *overlap_item;
         }
         overlap.copy_from_slice(&self.output_buf[self.fft_size_out..]);
+        &self.output_buf[0..self.fft_size_out]
+    }
+
+    /// Store the spectrum of the latest input chunk in a slot of the delay line of a channel.
+    /// The complete spectrum is stored, also the upper half given by symmetry,
+    /// since the folding needs all the frequencies.
+    fn store_spectrum(&mut self, channel: usize, slot: usize) {
+        let len = 2 * self.fft_size_in;
+        let spectrum = &mut self.spectra[channel][slot * len..(slot + 1) * len];
+        spectrum[0..=self.fft_size_in].copy_from_slice(&self.input_f);
+        let (lower, upper) = spectrum.split_at_mut(self.fft_size_in + 1);
+        for (item, mirrored) in upper
+            .iter_mut()
+            .zip(lower[1..self.fft_size_in].iter().rev())
+        {
+            *item = mirrored.conj();
+        }
+    }
+
+    /// Multiply the spectra in the delay line of a channel with the partitions of the filter,
+    /// and accumulate the products in the output spectrum.
+    ///
+    /// A chunk zero padded to twice its length is, after inserting zeros between the frames
+    /// to get the rate of the partitions, transformed to the same spectrum repeated several times.
+    /// Each output frequency is then the sum of the products at a set of frequencies
+    /// spaced by the output transform length, since keeping only every `phases`-th frame
+    /// of the filtered signal folds its spectrum down to the output rate.
+    /// These are stored one after the other for each output frequency, see [partition_spectra].
+    fn accumulate_partitions(&mut self, channel: usize) {
+        let len_in = 2 * self.fft_size_in;
+        let bins = self.fft_size_out + 1;
+        let filter_f = self.filter_f.as_ref().as_ref();
+        let phases = filter_f.len() / (self.filter_units * bins);
+        let spectra = &self.spectra[channel];
+        let newest = self.newest[channel];
+        for val in self.output_f.iter_mut() {
+            *val = Complex::zero();
+        }
+        for unit in 0..self.filter_units {
+            let slot = (newest + self.filter_units - unit) % self.filter_units;
+            let spectrum = &spectra[slot * len_in..(slot + 1) * len_in];
+            for phase in 0..phases {
+                let start = (unit * phases + phase) * bins;
+                let partition = &filter_f[start..start + bins];
+                let mut bin = 0;
+                let mut index = phase * 2 * self.fft_size_out % len_in;
+                while bin < bins {
+                    let frames = (bins - bin).min(len_in - index);
+                    self.output_f[bin..bin + frames]
+                        .iter_mut()
+                        .zip(spectrum[index..index + frames].iter())
+                        .zip(partition[bin..bin + frames].iter())
+                        .for_each(|((out, spec), filt)| *out += *spec * *filt);
+                    bin += frames;
+                    index = 0;
+                }
+            }
+        }
+        // The spectrum of a real signal is real at zero and at the Nyquist frequency.
+        self.output_f[0].im = T::zero();
+        self.output_f[self.fft_size_out].im = T::zero();
+    }
+
+    /// Recalculate the delay lines of a partitioned filter from the input frames
+    /// stored in the overlap buffers, after these have been reset or restored.
+    fn load_overlaps(&mut self, overlaps: &[Vec<T>]) {
+        if self.filter_units == 1 {
+            return;
+        }
+        for (channel, overlap) in overlaps.iter().enumerate() {
+            for (slot, frames) in overlap[self.fft_size_out..]
+                .chunks(self.fft_size_in)
+                .enumerate()
+            {
+                self.input_buf[0..self.fft_size_in].copy_from_slice(frames);
+                for item in self.input_buf.iter_mut().skip(self.fft_size_in) {
+                    *item = T::zero();
+                }
+                self.fft
+                    .process_with_scratch(
+                        &mut self.input_buf,
+                        &mut self.input_f,
+                        &mut self.scratch_fw,
+                    )
+                    .unwrap();
+                self.store_spectrum(channel, slot);
+            }
+            // The next chunk goes in the last slot.
+            self.newest[channel] = self.filter_units - 2;
+        }
+    }
+}
+
+/// Make the folded spectra of the partitions of a filter of `filter_units * fft_size_in` frames.
+///
+/// The filter is designed at the least common multiple of the input and output sample rates,
+/// `factor` times the input rate, as the prototype filter of a polyphase resampler.
+/// Each partition is transformed at twice its length, the same as a zero padded input chunk
+/// after inserting `factor - 1` zeros between the frames.
+/// For each partition and each of the `phases` frequencies that fold into an output frequency,
+/// the spectrum at the output frequencies is stored, including the scaling of both transforms.
+fn partition_spectra<T>(
+    fft_size_in: usize,
+    fft_size_out: usize,
+    filter_units: usize,
+    cutoff: f32,
+    window: WindowFunction,
+    planner: &mut RealFftPlanner<T>,
+) -> Vec<Complex<T>>
+where
+    T: Sample,
+{
+    let gcd = integer::gcd(fft_size_in, fft_size_out);
+    let factor = fft_size_out / gcd;
+    let phases = fft_size_in / gcd;
+    let unit_len = fft_size_in * factor;
+    let transform_len = 2 * unit_len;
+    let bins = fft_size_out + 1;
+    let sincs = make_sincs::<T>(
+        filter_units * fft_size_in,
+        factor,
+        cutoff,
+        window,
+        SincPhase::Linear,
+    );
+    let fft = planner.plan_fft_forward(transform_len);
+    let mut filter_t: Vec<T> = vec![T::zero(); transform_len];
+    let mut spectrum: Vec<Complex<T>> = vec![Complex::zero(); unit_len + 1];
+    let mut filter_f: Vec<Complex<T>> = Vec::with_capacity(filter_units * phases * bins);
+    for unit in 0..filter_units {
+        for (n, f) in filter_t.iter_mut().enumerate() {
+            *f = if n < unit_len {
+                // Undo the splitting of the prototype into the sincs.
+                let point = unit * unit_len + n;
+                sincs[factor - 1 - point % factor][point / factor] / T::coerce(transform_len)
+            } else {
+                T::zero()
+            };
+        }
+        fft.process(&mut filter_t, &mut spectrum).unwrap();
+        for phase in 0..phases {
+            for bin in 0..bins {
+                let index = bin + 2 * phase * fft_size_out;
+                filter_f.push(if index <= unit_len {
+                    spectrum[index]
+                } else {
+                    spectrum[transform_len - index].conj()
+                });
+            }
+        }
+    }
+    filter_f
+}
+
+/// The largest FFT input or output size accepted by the synchronous resamplers.
+const MAX_FFT_SIZE: usize = 1 << 20;
+
+/// Check that a user-supplied relative cutoff frequency is > 0 and <= 1.
+fn validate_cutoff(f_cutoff: Option<f32>) -> Result<(), ResamplerConstructionError> {
+    match f_cutoff {
+        Some(f_cutoff) if !(f_cutoff > 0.0 && f_cutoff <= 1.0) => {
+            Err(ResamplerConstructionError::InvalidCutoff(f_cutoff))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Reduce a resampling ratio to the smallest possible output and input sizes of an FFT unit.
+fn reduce_ratio(
+    numerator: u64,
+    denominator: u64,
+) -> Result<(usize, usize), ResamplerConstructionError> {
+    if numerator == 0 || denominator == 0 {
+        return Err(ResamplerConstructionError::InvalidRationalRatio {
+            numerator,
+            denominator,
+        });
+    }
+    let gcd = integer::gcd(numerator, denominator);
+    let size_out = usize::try_from(numerator / gcd).unwrap_or(usize::MAX);
+    let size_in = usize::try_from(denominator / gcd).unwrap_or(usize::MAX);
+    scale_fft_sizes(size_in, size_out, 1)?;
+    Ok((size_out, size_in))
+}
+
+/// Multiply the input and output sizes of an FFT unit by `factor`,
+/// and check that the results are not larger than [MAX_FFT_SIZE].
+fn scale_fft_sizes(
+    size_in: usize,
+    size_out: usize,
+    factor: usize,
+) -> Result<(usize, usize), ResamplerConstructionError> {
+    match size_in.max(size_out).checked_mul(factor) {
+        Some(required) if required <= MAX_FFT_SIZE => Ok((size_in * factor, size_out * factor)),
+        required => Err(ResamplerConstructionError::FftTooLong {
+            required: required.unwrap_or(usize::MAX),
+            max: MAX_FFT_SIZE,
+        }),
+    }
+}
+
+/// Get the number of FFT units needed for a filter of at least `filter_length` frames.
+/// Each unit is transformed at twice its length, which must not be larger than [MAX_FFT_SIZE].
+/// A filter that is longer than one unit is split into partitions,
+/// that are designed at the least common multiple of the input and output sample rates.
+/// The total length of the partitions at this rate must not be larger than [MAX_FFT_SIZE] either.
+fn filter_units(
+    filter_length: usize,
+    fft_size_in: usize,
+    fft_size_out: usize,
+) -> Result<usize, ResamplerConstructionError> {
+    let units =
+        (filter_length / fft_size_in + usize::from(filter_length % fft_size_in != 0)).max(1);
+    scale_fft_sizes(fft_size_in, fft_size_out, 2)?;
+    if units > 1 {
+        let factor = fft_size_out / integer::gcd(fft_size_in, fft_size_out);
+        match units
+            .checked_mul(fft_size_in)
+            .and_then(|len| len.checked_mul(factor))
+        {
+            Some(required) if required <= MAX_FFT_SIZE => {}
+            required => {
+                return Err(ResamplerConstructionError::FftTooLong {
+                    required: required.unwrap_or(usize::MAX),
+                    max: MAX_FFT_SIZE,
+                })
+            }
+        }
     }
+    Ok(units)
 }
 
 impl<T> FftFixedInOut<T>
@@ -203,23 +576,132 @@ where
         sample_rate_output: usize,
         chunk_size_in: usize,
         nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        // A filter length of a single frame gives a filter as long as the FFT.
+        Self::new_with_filter_length(
+            sample_rate_input,
+            sample_rate_output,
+            chunk_size_in,
+            1,
+            nbr_channels,
+        )
+    }
+
+    /// Create a new FftFixedInOut, with an antialiasing filter that is longer than the FFT.
+    ///
+    /// The filter is split into partitions as long as the chunk, and is applied
+    /// with a uniformly partitioned overlap-add convolution.
+    /// This allows using a long and steep filter together with a small chunk size,
+    /// at the cost of a longer delay and more processing per chunk.
+    /// Each chunk still needs only one FFT and one inverse FFT of twice the chunk size,
+    /// while the spectrum products grow with the number of partitions.
+    /// For example, a chunk of 64 frames with a filter of 4096 frames uses 64 partitions.
+    ///
+    /// Parameters are:
+    /// - `sample_rate_input`: Input sample rate, must be > 0.
+    /// - `sample_rate_output`: Output sample rate, must be > 0.
+    /// - `chunk_size_in`: desired length of input data in frames, actual value may be different.
+    /// - `filter_length`: minimum length of the antialiasing filter in input frames,
+    ///   rounded up to a multiple of the actual chunk size.
+    ///   The delay of the resampler is half the filter length.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_filter_length(
+        sample_rate_input: usize,
+        sample_rate_output: usize,
+        chunk_size_in: usize,
+        filter_length: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        Self::new_with_filter(
+            sample_rate_input,
+            sample_rate_output,
+            chunk_size_in,
+            FftFilterParameters {
+                filter_len: filter_length,
+                f_cutoff: None,
+                window: WindowFunction::BlackmanHarris2,
+            },
+            nbr_channels,
+        )
+    }
+
+    /// Create a new FftFixedInOut, with a custom antialiasing filter.
+    ///
+    /// The filter is a windowed sinc, see [FftFilterParameters] for the available options.
+    /// When the filter is longer than the chunks, each chunk is zero padded
+    /// to make room for the complete response of the filter,
+    /// and the overlapping tails are added to the following chunks.
+    ///
+    /// Parameters are:
+    /// - `sample_rate_input`: Input sample rate, must be > 0.
+    /// - `sample_rate_output`: Output sample rate, must be > 0.
+    /// - `chunk_size_in`: desired length of input data in frames, actual value may be different.
+    /// - `parameters`: Parameters for the antialiasing filter, see `FftFilterParameters`.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_filter(
+        sample_rate_input: usize,
+        sample_rate_output: usize,
+        chunk_size_in: usize,
+        parameters: FftFilterParameters,
+        nbr_channels: usize,
     ) -> Result<Self, ResamplerConstructionError> {
         validate_sample_rates(sample_rate_input, sample_rate_output)?;
+        Self::new_with_ratio(
+            sample_rate_output as u64,
+            sample_rate_input as u64,
+            chunk_size_in,
+            parameters,
+            nbr_channels,
+        )
+    }
 
+    /// Create a new FftFixedInOut, with the resampling ratio given as an exact fraction
+    /// instead of as a pair of sample rates.
+    /// The ratio is the output sample rate divided by the input sample rate,
+    /// which allows sample rates that are not whole numbers.
+    /// For example, converting 48000 Hz to 48000.5 Hz uses a ratio of 96001/96000,
+    /// and the NTSC pull-down from 44100 Hz to 44100 * 1000/1001 Hz uses 1000/1001.
+    ///
+    /// The fraction is reduced to its lowest terms, and the FFT sizes are multiples
+    /// of the reduced numerator and denominator.
+    /// If the transforms, or a partitioned filter designed at the common rate
+    /// of the input and output, are longer than the maximum of 2^20 frames,
+    /// [ResamplerConstructionError::FftTooLong] is returned.
+    ///
+    /// Parameters are:
+    /// - `ratio_numerator`: Numerator of the resampling ratio, must be > 0.
+    /// - `ratio_denominator`: Denominator of the resampling ratio, must be > 0.
+    /// - `chunk_size_in`: desired length of input data in frames, actual value may be different.
+    /// - `parameters`: Parameters for the antialiasing filter, see `FftFilterParameters`.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_ratio(
+        ratio_numerator: u64,
+        ratio_denominator: u64,
+        chunk_size_in: usize,
+        parameters: FftFilterParameters,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
         debug!(
-            "Create new FftFixedInOut, sample_rate_input: {}, sample_rate_output: {} chunk_size_in: {}, channels: {}",
-            sample_rate_input, sample_rate_output, chunk_size_in, nbr_channels
+            "Create new FftFixedInOut, ratio: {}/{}, chunk_size_in: {}, filter: {:?}, channels: {}",
+            ratio_numerator, ratio_denominator, chunk_size_in, parameters, nbr_channels
         );
 
-        let gcd = integer::gcd(sample_rate_input, sample_rate_output);
-        let min_chunk_in = sample_rate_input / gcd;
+        validate_cutoff(parameters.f_cutoff)?;
+        let (min_chunk_out, min_chunk_in) = reduce_ratio(ratio_numerator, ratio_denominator)?;
         let fft_chunks = (chunk_size_in as f32 / min_chunk_in as f32).ceil() as usize;
-        let fft_size_out = fft_chunks * sample_rate_output / gcd;
-        let fft_size_in = fft_chunks * sample_rate_input / gcd;
+        let (fft_size_in, fft_size_out) = scale_fft_sizes(min_chunk_in, min_chunk_out, fft_chunks)?;
+        let filter_units = filter_units(parameters.filter_len, fft_size_in, fft_size_out)?;
 
-        let resampler = FftResampler::<T>::new(fft_size_in, fft_size_out);
+        let resampler = FftResampler::<T>::new(
+            fft_size_in,
+            fft_size_out,
+            filter_units,
+            parameters.f_cutoff,
+            parameters.window,
+            nbr_channels,
+        );
 
-        let overlaps: Vec<Vec<T>> = vec![vec![T::zero(); fft_size_out]; nbr_channels];
+        let overlaps: Vec<Vec<T>> = vec![vec![T::zero(); resampler.overlap_len()]; nbr_channels];
 
         let channel_mask = vec![true; nbr_channels];
 
@@ -231,20 +713,68 @@ where
             overlaps,
             resampler,
             channel_mask,
+            frame_counter: FrameCounter::default(),
         })
     }
+
+    /// Take a snapshot of the internal state of the resampler,
+    /// see [FftResamplerState] for details.
+    pub fn state(&self) -> FftResamplerState<T> {
+        FftResamplerState {
+            overlaps: self.overlaps.clone(),
+            buffers: Vec::new(),
+            saved_frames: 0,
+            chunk_size: self.chunk_size_in,
+            output_frames_expected: self.frame_counter.expected,
+            output_frames_produced: self.frame_counter.produced,
+        }
+    }
+
+    /// Restore the internal state from a snapshot taken with [state](FftFixedInOut::state).
+    /// The resampler must have been created with the same parameters
+    /// as the one the snapshot was taken from.
+    /// If the snapshot does not match, an error is returned and the state is left unchanged.
+    pub fn set_state(&mut self, state: &FftResamplerState<T>) -> ResampleResult<()> {
+        validate_state_buffer(&self.overlaps, &state.overlaps)?;
+        validate_state_frames(state.output_frames_expected)?;
+        if !state.buffers.is_empty()
+            || state.saved_frames != 0
+            || state.chunk_size != self.chunk_size_in
+        {
+            return Err(ResampleError::IncompatibleState);
+        }
+        for (overlap, saved) in self.overlaps.iter_mut().zip(state.overlaps.iter()) {
+            overlap.copy_from_slice(saved);
+        }
+        self.resampler.load_overlaps(&self.overlaps);
+        self.frame_counter = FrameCounter {
+            expected: state.output_frames_expected,
+            produced: state.output_frames_produced,
+            ..Default::default()
+        };
+        Ok(())
+    }
+
+    /// The resampling ratio, output frames per input frame.
+    fn ratio(&self) -> f64 {
+        self.chunk_size_out as f64 / self.chunk_size_in as f64
+    }
 }
 
 impl<T> Resampler<T> for FftFixedInOut<T>
 where
     T: Sample,
 {
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    fn process_into_buffer<Vin, Vout>(
         &mut self,
-        wave_in: &[Vin],
-        wave_out: &mut [Vout],
+        wave_in: &Vin,
+        wave_out: &mut Vout,
         active_channels_mask: Option<&[bool]>,
-    ) -> ResampleResult<(usize, usize)> {
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -262,13 +792,17 @@ where
 
         for (channel, active) in self.channel_mask.iter().enumerate() {
             if *active {
-                self.resampler.resample_unit(
-                    &wave_in[channel].as_ref()[..self.chunk_size_in],
-                    &mut wave_out[channel].as_mut()[..self.chunk_size_out],
-                    &mut self.overlaps[channel],
-                )
+                wave_in.copy_from_channel_to_slice(channel, 0, self.resampler.input_unit());
+                wave_out.copy_from_slice_to_channel(
+                    channel,
+                    0,
+                    self.resampler
+                        .resample_unit(channel, &mut self.overlaps[channel]),
+                );
             }
         }
+        self.frame_counter
+            .add(self.chunk_size_in, self.chunk_size_out, self.ratio());
         Ok((self.chunk_size_in, self.chunk_size_out))
     }
 
@@ -293,7 +827,51 @@ where
     }
 
     fn output_delay(&self) -> usize {
-        self.chunk_size_out / 2
+        self.resampler.output_delay()
+    }
+
+    fn process_partial_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let frames = partial_input_frames(wave_in, self.input_frames_next());
+        let (frames_in, frames_out) = self.process_into_buffer(
+            &ZeroPadded::new(wave_in, self.nbr_channels),
+            wave_out,
+            active_channels_mask,
+        )?;
+        self.frame_counter
+            .remove_padding(frames_in - frames, self.ratio());
+        Ok((frames_in, frames_out))
+    }
+
+    fn output_frames_remaining(&self) -> usize {
+        self.frame_counter.remaining(self.output_delay())
+    }
+
+    fn flush_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        flush_resampler(
+            self,
+            |resampler| resampler.frame_counter,
+            wave_in,
+            wave_out,
+            active_channels_mask,
+        )
     }
 
     /// Update the resample ratio. This is not supported by this resampler and
@@ -309,9 +887,11 @@ where
     }
 
     fn reset(&mut self) {
+        self.frame_counter.reset();
         self.overlaps
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
+        self.resampler.load_overlaps(&self.overlaps);
         self.channel_mask.iter_mut().for_each(|val| *val = true);
     }
 }
@@ -334,24 +914,132 @@ where
         chunk_size_out: usize,
         sub_chunks: usize,
         nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        // A filter length of a single frame gives a filter as long as the FFT.
+        Self::new_with_filter_length(
+            sample_rate_input,
+            sample_rate_output,
+            chunk_size_out,
+            sub_chunks,
+            1,
+            nbr_channels,
+        )
+    }
+
+    /// Create a new FftFixedOut, with an antialiasing filter that is longer than the FFT.
+    ///
+    /// The filter is split into partitions as long as the subchunk, and is applied
+    /// with a uniformly partitioned overlap-add convolution.
+    /// This allows using a long and steep filter together with small subchunks,
+    /// at the cost of a longer delay and more processing per subchunk.
+    /// Each subchunk still needs only one FFT and one inverse FFT of twice the subchunk size,
+    /// while the spectrum products grow with the number of partitions.
+    ///
+    /// Parameters are:
+    /// - `sample_rate_input`: Input sample rate, must be > 0.
+    /// - `sample_rate_output`: Output sample rate, must be > 0.
+    /// - `chunk_size_out`: length of output data in frames.
+    /// - `sub_chunks`: desired number of subchunks for processing, actual number may be different.
+    /// - `filter_length`: minimum length of the antialiasing filter in input frames,
+    ///   rounded up to a multiple of the subchunk size.
+    ///   The delay of the resampler is half the filter length.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_filter_length(
+        sample_rate_input: usize,
+        sample_rate_output: usize,
+        chunk_size_out: usize,
+        sub_chunks: usize,
+        filter_length: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        Self::new_with_filter(
+            sample_rate_input,
+            sample_rate_output,
+            chunk_size_out,
+            sub_chunks,
+            FftFilterParameters {
+                filter_len: filter_length,
+                f_cutoff: None,
+                window: WindowFunction::BlackmanHarris2,
+            },
+            nbr_channels,
+        )
+    }
+
+    /// Create a new FftFixedOut, with a custom antialiasing filter.
+    ///
+    /// The filter is a windowed sinc, see [FftFilterParameters] for the available options.
+    /// When the filter is longer than the subchunks, each subchunk is zero padded
+    /// to make room for the complete response of the filter,
+    /// and the overlapping tails are added to the following subchunks.
+    ///
+    /// Parameters are:
+    /// - `sample_rate_input`: Input sample rate, must be > 0.
+    /// - `sample_rate_output`: Output sample rate, must be > 0.
+    /// - `chunk_size_out`: length of output data in frames.
+    /// - `sub_chunks`: desired number of subchunks for processing, actual number may be different.
+    /// - `parameters`: Parameters for the antialiasing filter, see `FftFilterParameters`.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_filter(
+        sample_rate_input: usize,
+        sample_rate_output: usize,
+        chunk_size_out: usize,
+        sub_chunks: usize,
+        parameters: FftFilterParameters,
+        nbr_channels: usize,
     ) -> Result<Self, ResamplerConstructionError> {
         validate_sample_rates(sample_rate_input, sample_rate_output)?;
+        Self::new_with_ratio(
+            sample_rate_output as u64,
+            sample_rate_input as u64,
+            chunk_size_out,
+            sub_chunks,
+            parameters,
+            nbr_channels,
+        )
+    }
 
-        let gcd = integer::gcd(sample_rate_input, sample_rate_output);
-        let min_chunk_out = sample_rate_output / gcd;
+    /// Create a new FftFixedOut, with the resampling ratio given as an exact fraction
+    /// of the output and input sample rates.
+    /// See [FftFixedInOut::new_with_ratio] for details.
+    ///
+    /// Parameters are:
+    /// - `ratio_numerator`: Numerator of the resampling ratio, must be > 0.
+    /// - `ratio_denominator`: Denominator of the resampling ratio, must be > 0.
+    /// - `chunk_size_out`: length of output data in frames.
+    /// - `sub_chunks`: desired number of subchunks for processing, actual number may be different.
+    /// - `parameters`: Parameters for the antialiasing filter, see `FftFilterParameters`.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_ratio(
+        ratio_numerator: u64,
+        ratio_denominator: u64,
+        chunk_size_out: usize,
+        sub_chunks: usize,
+        parameters: FftFilterParameters,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        validate_cutoff(parameters.f_cutoff)?;
+        let (min_chunk_out, min_chunk_in) = reduce_ratio(ratio_numerator, ratio_denominator)?;
         let wanted_subsize = chunk_size_out / sub_chunks;
         let fft_chunks = (wanted_subsize as f32 / min_chunk_out as f32).ceil() as usize;
-        let fft_size_out = fft_chunks * sample_rate_output / gcd;
-        let fft_size_in = fft_chunks * sample_rate_input / gcd;
+        let (fft_size_in, fft_size_out) = scale_fft_sizes(min_chunk_in, min_chunk_out, fft_chunks)?;
+        let filter_units = filter_units(parameters.filter_len, fft_size_in, fft_size_out)?;
 
-        let resampler = FftResampler::<T>::new(fft_size_in, fft_size_out);
+        let resampler = FftResampler::<T>::new(
+            fft_size_in,
+            fft_size_out,
+            filter_units,
+            parameters.f_cutoff,
+            parameters.window,
+            nbr_channels,
+        );
 
         debug!(
-            "Create new FftFixedOut, sample_rate_input: {}, sample_rate_output: {} chunk_size_in: {}, channels: {}, fft_size_in: {}, fft_size_out: {}",
-            sample_rate_input, sample_rate_output, chunk_size_out, nbr_channels, fft_size_in, fft_size_out
+            "Create new FftFixedOut, ratio: {}/{}, chunk_size_out: {}, channels: {}, fft_size_in: {}, fft_size_out: {}, filter: {:?}",
+            ratio_numerator, ratio_denominator, chunk_size_out, nbr_channels, fft_size_in, fft_size_out, parameters
         );
 
-        let overlaps: Vec<Vec<T>> = vec![vec![T::zero(); fft_size_out]; nbr_channels];
+        let overlaps: Vec<Vec<T>> = vec![vec![T::zero(); resampler.overlap_len()]; nbr_channels];
         let output_buffers: Vec<Vec<T>> =
             vec![vec![T::zero(); chunk_size_out + fft_size_out]; nbr_channels];
 
@@ -364,6 +1052,7 @@ where
         Ok(FftFixedOut {
             nbr_channels,
             chunk_size_out,
+            max_chunk_size_out: chunk_size_out,
             fft_size_in,
             fft_size_out,
             overlaps,
@@ -372,20 +1061,82 @@ where
             frames_needed,
             resampler,
             channel_mask,
+            frame_counter: FrameCounter::default(),
         })
     }
+
+    /// Take a snapshot of the internal state of the resampler,
+    /// see [FftResamplerState] for details.
+    pub fn state(&self) -> FftResamplerState<T> {
+        FftResamplerState {
+            overlaps: self.overlaps.clone(),
+            buffers: self.output_buffers.clone(),
+            saved_frames: self.saved_frames,
+            chunk_size: self.chunk_size_out,
+            output_frames_expected: self.frame_counter.expected,
+            output_frames_produced: self.frame_counter.produced,
+        }
+    }
+
+    /// Restore the internal state from a snapshot taken with [state](FftFixedOut::state).
+    /// The resampler must have been created with the same parameters
+    /// as the one the snapshot was taken from.
+    /// If the snapshot does not match, an error is returned and the state is left unchanged.
+    pub fn set_state(&mut self, state: &FftResamplerState<T>) -> ResampleResult<()> {
+        validate_state_buffer(&self.overlaps, &state.overlaps)?;
+        validate_state_frames(state.output_frames_expected)?;
+        validate_state_buffer(&self.output_buffers, &state.buffers)?;
+        if state.saved_frames > self.fft_size_out
+            || state.chunk_size == 0
+            || state.chunk_size > self.max_chunk_size_out
+        {
+            return Err(ResampleError::IncompatibleState);
+        }
+        for (overlap, saved) in self.overlaps.iter_mut().zip(state.overlaps.iter()) {
+            overlap.copy_from_slice(saved);
+        }
+        self.resampler.load_overlaps(&self.overlaps);
+        for (buf, saved) in self.output_buffers.iter_mut().zip(state.buffers.iter()) {
+            buf.copy_from_slice(saved);
+        }
+        self.saved_frames = state.saved_frames;
+        self.chunk_size_out = state.chunk_size;
+        self.update_frames_needed();
+        self.frame_counter = FrameCounter {
+            expected: state.output_frames_expected,
+            produced: state.output_frames_produced,
+            ..Default::default()
+        };
+        Ok(())
+    }
+
+    /// The resampling ratio, output frames per input frame.
+    fn ratio(&self) -> f64 {
+        self.fft_size_out as f64 / self.fft_size_in as f64
+    }
+
+    /// Calculate the number of input frames needed for the next chunk.
+    fn update_frames_needed(&mut self) {
+        let frames_needed_out = self.chunk_size_out.saturating_sub(self.saved_frames);
+        let chunks_needed = (frames_needed_out as f32 / self.fft_size_out as f32).ceil() as usize;
+        self.frames_needed = chunks_needed * self.fft_size_in;
+    }
 }
 
 impl<T> Resampler<T> for FftFixedOut<T>
 where
     T: Sample,
 {
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    fn process_into_buffer<Vin, Vout>(
         &mut self,
-        wave_in: &[Vin],
-        wave_out: &mut [Vout],
+        wave_in: &Vin,
+        wave_out: &mut Vout,
         active_channels_mask: Option<&[bool]>,
-    ) -> ResampleResult<(usize, usize)> {
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
@@ -403,16 +1154,19 @@ where
 
         for (chan, active) in self.channel_mask.iter().enumerate() {
             if *active {
-                debug_assert!(self.chunk_size_out <= wave_out[chan].as_mut().len());
-                for (in_chunk, out_chunk) in wave_in[chan].as_ref()[..self.frames_needed]
-                    .chunks(self.fft_size_in)
-                    .zip(
-                        self.output_buffers[chan][self.saved_frames..]
-                            .chunks_mut(self.fft_size_out),
-                    )
+                for (chunk, out_chunk) in self.output_buffers[chan][self.saved_frames..]
+                    .chunks_mut(self.fft_size_out)
+                    .take(self.frames_needed / self.fft_size_in)
+                    .enumerate()
                 {
-                    self.resampler
-                        .resample_unit(in_chunk, out_chunk, &mut self.overlaps[chan]);
+                    wave_in.copy_from_channel_to_slice(
+                        chan,
+                        chunk * self.fft_size_in,
+                        self.resampler.input_unit(),
+                    );
+                    out_chunk.copy_from_slice(
+                        self.resampler.resample_unit(chan, &mut self.overlaps[chan]),
+                    );
                 }
             }
         }
@@ -424,8 +1178,11 @@ where
             self.saved_frames = processed_frames - self.chunk_size_out;
             for (chan, active) in self.channel_mask.iter().enumerate() {
                 if *active {
-                    wave_out[chan].as_mut()[..self.chunk_size_out]
-                        .copy_from_slice(&self.output_buffers[chan][..self.chunk_size_out]);
+                    wave_out.copy_from_slice_to_channel(
+                        chan,
+                        0,
+                        &self.output_buffers[chan][..self.chunk_size_out],
+                    );
                     self.output_buffers[chan].copy_within(
                         self.chunk_size_out..(self.chunk_size_out + self.saved_frames),
                         0,
@@ -436,19 +1193,16 @@ where
             self.saved_frames = processed_frames;
         }
         // Calculate number of needed frames from next round.
-        let frames_needed_out = if self.chunk_size_out > self.saved_frames {
-            self.chunk_size_out - self.saved_frames
-        } else {
-            0
-        };
         let input_frames_used = self.frames_needed;
-        let chunks_needed = (frames_needed_out as f32 / self.fft_size_out as f32).ceil() as usize;
-        self.frames_needed = chunks_needed * self.fft_size_in;
+        self.update_frames_needed();
+        self.frame_counter
+            .add(input_frames_used, self.chunk_size_out, self.ratio());
         Ok((input_frames_used, self.chunk_size_out))
     }
 
     fn input_frames_max(&self) -> usize {
-        (self.chunk_size_out as f32 / self.fft_size_out as f32).ceil() as usize * self.fft_size_in
+        (self.max_chunk_size_out as f32 / self.fft_size_out as f32).ceil() as usize
+            * self.fft_size_in
     }
 
     fn input_frames_next(&self) -> usize {
@@ -460,15 +1214,59 @@ where
     }
 
     fn output_frames_max(&self) -> usize {
-        self.chunk_size_out
+        self.max_chunk_size_out
     }
 
     fn output_frames_next(&self) -> usize {
-        self.output_frames_max()
+        self.chunk_size_out
     }
 
     fn output_delay(&self) -> usize {
-        self.fft_size_out / 2
+        self.resampler.output_delay()
+    }
+
+    fn process_partial_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let frames = partial_input_frames(wave_in, self.input_frames_next());
+        let (frames_in, frames_out) = self.process_into_buffer(
+            &ZeroPadded::new(wave_in, self.nbr_channels),
+            wave_out,
+            active_channels_mask,
+        )?;
+        self.frame_counter
+            .remove_padding(frames_in - frames, self.ratio());
+        Ok((frames_in, frames_out))
+    }
+
+    fn output_frames_remaining(&self) -> usize {
+        self.frame_counter.remaining(self.output_delay())
+    }
+
+    fn flush_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        flush_resampler(
+            self,
+            |resampler| resampler.frame_counter,
+            wave_in,
+            wave_out,
+            active_channels_mask,
+        )
     }
 
     /// Update the resample ratio. This is not supported by this resampler and
@@ -484,16 +1282,30 @@ where
     }
 
     fn reset(&mut self) {
+        self.frame_counter.reset();
         self.overlaps
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
+        self.resampler.load_overlaps(&self.overlaps);
         self.output_buffers
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
         self.channel_mask.iter_mut().for_each(|val| *val = true);
         self.saved_frames = 0;
-        let chunks_needed = (self.chunk_size_out as f32 / self.fft_size_out as f32).ceil() as usize;
-        self.frames_needed = chunks_needed * self.fft_size_in;
+        self.chunk_size_out = self.max_chunk_size_out;
+        self.update_frames_needed();
+    }
+
+    fn set_chunk_size(&mut self, chunksize: usize) -> ResampleResult<()> {
+        if chunksize > self.max_chunk_size_out || chunksize == 0 {
+            return Err(ResampleError::InvalidChunkSize {
+                max: self.max_chunk_size_out,
+                requested: chunksize,
+            });
+        }
+        self.chunk_size_out = chunksize;
+        self.update_frames_needed();
+        Ok(())
     }
 }
 
@@ -515,23 +1327,131 @@ where
         chunk_size_in: usize,
         sub_chunks: usize,
         nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        // A filter length of a single frame gives a filter as long as the FFT.
+        Self::new_with_filter_length(
+            sample_rate_input,
+            sample_rate_output,
+            chunk_size_in,
+            sub_chunks,
+            1,
+            nbr_channels,
+        )
+    }
+
+    /// Create a new FftFixedIn, with an antialiasing filter that is longer than the FFT.
+    ///
+    /// The filter is split into partitions as long as the subchunk, and is applied
+    /// with a uniformly partitioned overlap-add convolution.
+    /// This allows using a long and steep filter together with small subchunks,
+    /// at the cost of a longer delay and more processing per subchunk.
+    /// Each subchunk still needs only one FFT and one inverse FFT of twice the subchunk size,
+    /// while the spectrum products grow with the number of partitions.
+    ///
+    /// Parameters are:
+    /// - `sample_rate_input`: Input sample rate, must be > 0.
+    /// - `sample_rate_output`: Output sample rate, must be > 0.
+    /// - `chunk_size_in`: length of input data in frames.
+    /// - `sub_chunks`: desired number of subchunks for processing, actual number used may be different.
+    /// - `filter_length`: minimum length of the antialiasing filter in input frames,
+    ///   rounded up to a multiple of the subchunk size.
+    ///   The delay of the resampler is half the filter length.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_filter_length(
+        sample_rate_input: usize,
+        sample_rate_output: usize,
+        chunk_size_in: usize,
+        sub_chunks: usize,
+        filter_length: usize,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        Self::new_with_filter(
+            sample_rate_input,
+            sample_rate_output,
+            chunk_size_in,
+            sub_chunks,
+            FftFilterParameters {
+                filter_len: filter_length,
+                f_cutoff: None,
+                window: WindowFunction::BlackmanHarris2,
+            },
+            nbr_channels,
+        )
+    }
+
+    /// Create a new FftFixedIn, with a custom antialiasing filter.
+    ///
+    /// The filter is a windowed sinc, see [FftFilterParameters] for the available options.
+    /// When the filter is longer than the subchunks, each subchunk is zero padded
+    /// to make room for the complete response of the filter,
+    /// and the overlapping tails are added to the following subchunks.
+    ///
+    /// Parameters are:
+    /// - `sample_rate_input`: Input sample rate, must be > 0.
+    /// - `sample_rate_output`: Output sample rate, must be > 0.
+    /// - `chunk_size_in`: length of input data in frames.
+    /// - `sub_chunks`: desired number of subchunks for processing, actual number used may be different.
+    /// - `parameters`: Parameters for the antialiasing filter, see `FftFilterParameters`.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_filter(
+        sample_rate_input: usize,
+        sample_rate_output: usize,
+        chunk_size_in: usize,
+        sub_chunks: usize,
+        parameters: FftFilterParameters,
+        nbr_channels: usize,
     ) -> Result<Self, ResamplerConstructionError> {
         validate_sample_rates(sample_rate_input, sample_rate_output)?;
+        Self::new_with_ratio(
+            sample_rate_output as u64,
+            sample_rate_input as u64,
+            chunk_size_in,
+            sub_chunks,
+            parameters,
+            nbr_channels,
+        )
+    }
 
-        let gcd = integer::gcd(sample_rate_input, sample_rate_output);
-        let min_chunk_in = sample_rate_input / gcd;
+    /// Create a new FftFixedIn, with the resampling ratio given as an exact fraction
+    /// of the output and input sample rates.
+    /// See [FftFixedInOut::new_with_ratio] for details.
+    ///
+    /// Parameters are:
+    /// - `ratio_numerator`: Numerator of the resampling ratio, must be > 0.
+    /// - `ratio_denominator`: Denominator of the resampling ratio, must be > 0.
+    /// - `chunk_size_in`: length of input data in frames.
+    /// - `sub_chunks`: desired number of subchunks for processing, actual number used may be different.
+    /// - `parameters`: Parameters for the antialiasing filter, see `FftFilterParameters`.
+    /// - `nbr_channels`: number of channels in input/output.
+    pub fn new_with_ratio(
+        ratio_numerator: u64,
+        ratio_denominator: u64,
+        chunk_size_in: usize,
+        sub_chunks: usize,
+        parameters: FftFilterParameters,
+        nbr_channels: usize,
+    ) -> Result<Self, ResamplerConstructionError> {
+        validate_cutoff(parameters.f_cutoff)?;
+        let (min_chunk_out, min_chunk_in) = reduce_ratio(ratio_numerator, ratio_denominator)?;
         let wanted_subsize = chunk_size_in / sub_chunks;
         let fft_chunks = (wanted_subsize as f32 / min_chunk_in as f32).ceil() as usize;
-        let fft_size_out = fft_chunks * sample_rate_output / gcd;
-        let fft_size_in = fft_chunks * sample_rate_input / gcd;
+        let (fft_size_in, fft_size_out) = scale_fft_sizes(min_chunk_in, min_chunk_out, fft_chunks)?;
+        let filter_units = filter_units(parameters.filter_len, fft_size_in, fft_size_out)?;
 
-        let resampler = FftResampler::<T>::new(fft_size_in, fft_size_out);
+        let resampler = FftResampler::<T>::new(
+            fft_size_in,
+            fft_size_out,
+            filter_units,
+            parameters.f_cutoff,
+            parameters.window,
+            nbr_channels,
+        );
         debug!(
-            "Create new FftFixedOut, sample_rate_input: {}, sample_rate_output: {} chunk_size_in: {}, channels: {}, fft_size_in: {}, fft_size_out: {}",
-            sample_rate_input, sample_rate_output, chunk_size_in, nbr_channels, fft_size_in, fft_size_out
+            "Create new FftFixedIn, ratio: {}/{}, chunk_size_in: {}, channels: {}, fft_size_in: {}, fft_size_out: {}, filter: {:?}",
+            ratio_numerator, ratio_denominator, chunk_size_in, nbr_channels, fft_size_in, fft_size_out, parameters
         );
 
-        let overlaps: Vec<Vec<T>> = vec![vec![T::zero(); fft_size_out]; nbr_channels];
+        let overlaps: Vec<Vec<T>> = vec![vec![T::zero(); resampler.overlap_len()]; nbr_channels];
         let input_buffers: Vec<Vec<T>> =
             vec![vec![T::zero(); chunk_size_in + fft_size_in]; nbr_channels];
 
@@ -542,6 +1462,7 @@ where
         Ok(FftFixedIn {
             nbr_channels,
             chunk_size_in,
+            max_chunk_size_in: chunk_size_in,
             fft_size_in,
             fft_size_out,
             overlaps,
@@ -549,30 +1470,81 @@ where
             saved_frames,
             resampler,
             channel_mask,
+            frame_counter: FrameCounter::default(),
         })
     }
+
+    /// Take a snapshot of the internal state of the resampler,
+    /// see [FftResamplerState] for details.
+    pub fn state(&self) -> FftResamplerState<T> {
+        FftResamplerState {
+            overlaps: self.overlaps.clone(),
+            buffers: self.input_buffers.clone(),
+            saved_frames: self.saved_frames,
+            chunk_size: self.chunk_size_in,
+            output_frames_expected: self.frame_counter.expected,
+            output_frames_produced: self.frame_counter.produced,
+        }
+    }
+
+    /// Restore the internal state from a snapshot taken with [state](FftFixedIn::state).
+    /// The resampler must have been created with the same parameters
+    /// as the one the snapshot was taken from.
+    /// If the snapshot does not match, an error is returned and the state is left unchanged.
+    pub fn set_state(&mut self, state: &FftResamplerState<T>) -> ResampleResult<()> {
+        validate_state_buffer(&self.overlaps, &state.overlaps)?;
+        validate_state_frames(state.output_frames_expected)?;
+        validate_state_buffer(&self.input_buffers, &state.buffers)?;
+        if state.saved_frames >= self.fft_size_in
+            || state.chunk_size == 0
+            || state.chunk_size > self.max_chunk_size_in
+        {
+            return Err(ResampleError::IncompatibleState);
+        }
+        for (overlap, saved) in self.overlaps.iter_mut().zip(state.overlaps.iter()) {
+            overlap.copy_from_slice(saved);
+        }
+        self.resampler.load_overlaps(&self.overlaps);
+        for (buf, saved) in self.input_buffers.iter_mut().zip(state.buffers.iter()) {
+            buf.copy_from_slice(saved);
+        }
+        self.saved_frames = state.saved_frames;
+        self.chunk_size_in = state.chunk_size;
+        self.frame_counter = FrameCounter {
+            expected: state.output_frames_expected,
+            produced: state.output_frames_produced,
+            ..Default::default()
+        };
+        Ok(())
+    }
+
+    /// The resampling ratio, output frames per input frame.
+    fn ratio(&self) -> f64 {
+        self.fft_size_out as f64 / self.fft_size_in as f64
+    }
 }
 
 impl<T> Resampler<T> for FftFixedIn<T>
 where
     T: Sample,
 {
-    fn process_into_buffer<Vin: AsRef<[T]>, Vout: AsMut<[T]>>(
+    fn process_into_buffer<Vin, Vout>(
         &mut self,
-        wave_in: &[Vin],
-        wave_out: &mut [Vout],
+        wave_in: &Vin,
+        wave_out: &mut Vout,
         active_channels_mask: Option<&[bool]>,
-    ) -> ResampleResult<(usize, usize)> {
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
         if let Some(mask) = active_channels_mask {
             self.channel_mask.copy_from_slice(mask);
         } else {
             update_mask_from_buffers(&mut self.channel_mask);
         };
 
-        let next_saved_frames = self.saved_frames + self.chunk_size_in;
-        let nbr_chunks_ready =
-            (next_saved_frames as f32 / self.fft_size_in as f32).floor() as usize;
-        let needed_len = nbr_chunks_ready * self.fft_size_out;
+        let needed_len = self.output_frames_next();
 
         validate_buffers(
             wave_in,
@@ -583,17 +1555,18 @@ where
             needed_len,
         )?;
 
+        let next_saved_frames = self.saved_frames + self.chunk_size_in;
+        let nbr_chunks_ready = next_saved_frames / self.fft_size_in;
+
         // Copy new samples to input buffer.
         for (chan, active) in self.channel_mask.iter().enumerate() {
             if *active {
-                for (input, buffer) in wave_in[chan].as_ref().iter().zip(
-                    self.input_buffers[chan]
-                        .iter_mut()
-                        .skip(self.saved_frames)
-                        .take(self.chunk_size_in),
-                ) {
-                    *buffer = *input;
-                }
+                wave_in.copy_from_channel_to_slice(
+                    chan,
+                    0,
+                    &mut self.input_buffers[chan]
+                        [self.saved_frames..self.saved_frames + self.chunk_size_in],
+                );
             }
         }
 
@@ -601,14 +1574,17 @@ where
 
         for (chan, active) in self.channel_mask.iter().enumerate() {
             if *active {
-                debug_assert!(needed_len <= wave_out[chan].as_mut().len());
-                for (in_chunk, out_chunk) in self.input_buffers[chan]
+                for (chunk, in_chunk) in self.input_buffers[chan]
                     .chunks(self.fft_size_in)
                     .take(nbr_chunks_ready)
-                    .zip(wave_out[chan].as_mut().chunks_mut(self.fft_size_out))
+                    .enumerate()
                 {
-                    self.resampler
-                        .resample_unit(in_chunk, out_chunk, &mut self.overlaps[chan]);
+                    self.resampler.input_unit().copy_from_slice(in_chunk);
+                    wave_out.copy_from_slice_to_channel(
+                        chan,
+                        chunk * self.fft_size_out,
+                        self.resampler.resample_unit(chan, &mut self.overlaps[chan]),
+                    );
                 }
             }
         }
@@ -625,11 +1601,13 @@ where
             }
         }
         self.saved_frames = extra;
+        self.frame_counter
+            .add(self.chunk_size_in, needed_len, self.ratio());
         Ok((self.chunk_size_in, needed_len))
     }
 
     fn input_frames_max(&self) -> usize {
-        self.chunk_size_in
+        self.max_chunk_size_in
     }
 
     fn input_frames_next(&self) -> usize {
@@ -642,7 +1620,7 @@ where
 
     fn output_frames_max(&self) -> usize {
         let max_stored_frames = self.fft_size_in - 1;
-        let max_available_frames = max_stored_frames + self.chunk_size_in;
+        let max_available_frames = max_stored_frames + self.max_chunk_size_in;
         let max_subchunks_to_process = max_available_frames / self.fft_size_in;
         max_subchunks_to_process * self.fft_size_out
     }
@@ -654,7 +1632,51 @@ where
     }
 
     fn output_delay(&self) -> usize {
-        self.fft_size_out / 2
+        self.resampler.output_delay()
+    }
+
+    fn process_partial_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<(usize, usize)>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        let frames = partial_input_frames(wave_in, self.input_frames_next());
+        let (frames_in, frames_out) = self.process_into_buffer(
+            &ZeroPadded::new(wave_in, self.nbr_channels),
+            wave_out,
+            active_channels_mask,
+        )?;
+        self.frame_counter
+            .remove_padding(frames_in - frames, self.ratio());
+        Ok((frames_in, frames_out))
+    }
+
+    fn output_frames_remaining(&self) -> usize {
+        self.frame_counter.remaining(self.output_delay())
+    }
+
+    fn flush_into_buffer<Vin, Vout>(
+        &mut self,
+        wave_in: Option<&Vin>,
+        wave_out: &mut Vout,
+        active_channels_mask: Option<&[bool]>,
+    ) -> ResampleResult<usize>
+    where
+        Vin: AudioInput<T> + ?Sized,
+        Vout: AudioOutput<T> + ?Sized,
+    {
+        flush_resampler(
+            self,
+            |resampler| resampler.frame_counter,
+            wave_in,
+            wave_out,
+            active_channels_mask,
+        )
     }
 
     /// Update the resample ratio. This is not supported by this resampler and
@@ -670,28 +1692,193 @@ where
     }
 
     fn reset(&mut self) {
+        self.frame_counter.reset();
         self.overlaps
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
+        self.resampler.load_overlaps(&self.overlaps);
         self.input_buffers
             .iter_mut()
             .for_each(|ch| ch.iter_mut().for_each(|s| *s = T::zero()));
         self.channel_mask.iter_mut().for_each(|val| *val = true);
         self.saved_frames = 0;
+        self.chunk_size_in = self.max_chunk_size_in;
+    }
+
+    fn set_chunk_size(&mut self, chunksize: usize) -> ResampleResult<()> {
+        if chunksize > self.max_chunk_size_in || chunksize == 0 {
+            return Err(ResampleError::InvalidChunkSize {
+                max: self.max_chunk_size_in,
+                requested: chunksize,
+            });
+        }
+        self.chunk_size_in = chunksize;
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::check_output;
-    use crate::synchro::{FftFixedIn, FftFixedInOut, FftFixedOut, FftResampler};
-    use crate::Resampler;
+    use crate::sinc::{make_sincs, SincPhase};
+    use crate::synchro::{
+        FftFilterParameters, FftFixedIn, FftFixedInOut, FftFixedOut, FftResampler,
+    };
+    use crate::{check_output, check_state};
+    use crate::{ResampleError, Resampler, ResamplerConstructionError, WindowFunction};
+    use num_integer as integer;
     use rand::Rng;
+    use std::f64::consts::PI;
+    use std::sync::Arc;
     use test_log::test;
 
+    #[test]
+    fn restore_state() {
+        let mut resampler = FftFixedIn::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        let mut restored = FftFixedIn::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        check_state!(resampler, restored);
+        let mut resampler = FftFixedOut::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        let mut restored = FftFixedOut::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        check_state!(resampler, restored);
+        let mut resampler = FftFixedInOut::<f64>::new(44100, 48000, 1000, 2).unwrap();
+        let mut restored = FftFixedInOut::<f64>::new(44100, 48000, 1000, 2).unwrap();
+        check_state!(resampler, restored);
+        let mut other = FftFixedInOut::<f64>::new(44100, 32000, 1000, 2).unwrap();
+        assert!(matches!(
+            other.set_state(&resampler.state()),
+            Err(ResampleError::IncompatibleState)
+        ));
+    }
+
+    #[test]
+    fn restore_state_long_filter() {
+        let mut resampler =
+            FftFixedIn::<f64>::new_with_filter_length(44100, 48000, 1000, 4, 2048, 2).unwrap();
+        let mut restored =
+            FftFixedIn::<f64>::new_with_filter_length(44100, 48000, 1000, 4, 2048, 2).unwrap();
+        check_state!(resampler, restored);
+        let mut other = FftFixedIn::<f64>::new(44100, 48000, 1000, 4, 2).unwrap();
+        assert!(matches!(
+            other.set_state(&resampler.state()),
+            Err(ResampleError::IncompatibleState)
+        ));
+    }
+
+    // A partitioned filter gives the same result as a polyphase resampler,
+    // with the filter designed at the least common multiple of the sample rates.
+    fn check_partitioned(rate_in: usize, rate_out: usize, chunk_size: usize, filter_len: usize) {
+        let parameters = FftFilterParameters {
+            filter_len,
+            f_cutoff: Some(0.9),
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler =
+            FftFixedInOut::<f64>::new_with_filter(rate_in, rate_out, chunk_size, parameters, 1)
+                .unwrap();
+        let size_in = resampler.input_frames_next();
+        let size_out = resampler.output_frames_next();
+        let units = (filter_len + size_in - 1) / size_in;
+        assert!(units > 1);
+        let gcd = integer::gcd(size_in, size_out);
+        let (factor, phases) = (size_out / gcd, size_in / gcd);
+        let cutoff = if size_in > size_out {
+            0.9 * size_out as f32 / size_in as f32
+        } else {
+            0.9
+        };
+        let sincs = make_sincs::<f64>(
+            units * size_in,
+            factor,
+            cutoff,
+            WindowFunction::BlackmanHarris2,
+            SincPhase::Linear,
+        );
+        let prototype: Vec<f64> = (0..units * size_in * factor)
+            .map(|n| sincs[factor - 1 - n % factor][n / factor])
+            .collect();
+
+        let mut rng = rand::thread_rng();
+        let input: Vec<f64> = (0..4 * units * size_in)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect();
+        let mut output = Vec::new();
+        for chunk in input.chunks(size_in) {
+            output.extend(resampler.process(&[chunk], None).unwrap().remove(0));
+        }
+        for (m, value) in output.iter().enumerate() {
+            let expected: f64 = input
+                .iter()
+                .enumerate()
+                .filter_map(|(n, x)| {
+                    (m * phases)
+                        .checked_sub(n * factor)
+                        .and_then(|index| prototype.get(index))
+                        .map(|h| x * h)
+                })
+                .sum();
+            assert!(
+                (value - expected).abs() < 1.0e-9,
+                "Frame {}: {} {}",
+                m,
+                value,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn partitioned_upsample() {
+        check_partitioned(48000, 96000, 64, 1024);
+    }
+
+    #[test]
+    fn partitioned_downsample() {
+        check_partitioned(96000, 48000, 64, 1024);
+    }
+
+    #[test]
+    fn partitioned_fractional() {
+        check_partitioned(44100, 48000, 147, 1024);
+        check_partitioned(48000, 44100, 160, 1024);
+    }
+
+    // Small chunks with a long filter give the same result
+    // as large chunks with a filter of the same length.
+    fn check_long_filter(rate_in: usize, rate_out: usize) {
+        let mut large = FftFixedInOut::<f64>::new(rate_in, rate_out, 1024, 1).unwrap();
+        let mut small =
+            FftFixedInOut::<f64>::new_with_filter_length(rate_in, rate_out, 64, 1024, 1).unwrap();
+        assert_eq!(small.input_frames_next(), 64);
+        assert_eq!(large.output_delay(), small.output_delay());
+        let mut rng = rand::thread_rng();
+        let input: Vec<f64> = (0..4096).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        let mut output_large = Vec::new();
+        for chunk in input.chunks(1024) {
+            output_large.extend(large.process(&[chunk], None).unwrap().remove(0));
+        }
+        let mut output_small = Vec::new();
+        for chunk in input.chunks(64) {
+            output_small.extend(small.process(&[chunk], None).unwrap().remove(0));
+        }
+        assert_eq!(output_large.len(), output_small.len());
+        for (n, (a, b)) in output_large.iter().zip(output_small.iter()).enumerate() {
+            assert!((a - b).abs() < 1.0e-6, "Frame {}: {} {}", n, a, b);
+        }
+    }
+
+    #[test]
+    fn long_filter_upsample() {
+        check_long_filter(48000, 96000);
+    }
+
+    #[test]
+    fn long_filter_downsample() {
+        check_long_filter(96000, 48000);
+    }
+
     #[test]
     fn resample_unit() {
-        let mut resampler = FftResampler::<f64>::new(147, 1000);
+        let mut resampler =
+            FftResampler::<f64>::new(147, 1000, 1, None, WindowFunction::BlackmanHarris2, 1);
         let mut wave_in = vec![0.0; 147];
 
         wave_in[0] = 0.3;
@@ -701,11 +1888,11 @@ mod tests {
         wave_in[4] = 0.7;
         wave_in[5] = 0.3;
 
-        let mut wave_out = vec![0.0; 1000];
         let mut overlap = vec![0.0; 1000];
-        resampler.resample_unit(&wave_in, &mut wave_out, &mut overlap);
+        resampler.input_unit().copy_from_slice(&wave_in);
+        let wave_out = resampler.resample_unit(0, &mut overlap);
         let vecsum = wave_out.iter().sum::<f64>();
-        let maxval = wave_out.iter().cloned().fold(0. / 0., f64::max);
+        let maxval = wave_out.iter().cloned().fold(f64::NAN, f64::max);
         assert!((vecsum - 4.0 * 1000.0 / 147.0).abs() < 1.0e-6);
         assert!((maxval - 1.0).abs() < 0.1);
     }
@@ -946,6 +2133,274 @@ mod tests {
         check_output!(resampler);
     }
 
+    #[test]
+    fn check_fo_output_custom_filter() {
+        let parameters = FftFilterParameters {
+            filter_len: 2048,
+            f_cutoff: Some(0.9),
+            window: WindowFunction::Kaiser { beta: 10.0 },
+        };
+        let mut resampler =
+            FftFixedOut::<f64>::new_with_filter(44100, 48000, 4096, 4, parameters, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    // Get the rms amplitude of a 20 kHz sine, resampled from 48 to 96 kHz.
+    fn resampled_rms(f_cutoff: f32) -> f64 {
+        let parameters = FftFilterParameters {
+            filter_len: 1024,
+            f_cutoff: Some(f_cutoff),
+            window: WindowFunction::BlackmanHarris2,
+        };
+        let mut resampler =
+            FftFixedInOut::<f64>::new_with_filter(48000, 96000, 256, parameters, 1).unwrap();
+        let mut output = Vec::new();
+        for chunk in 0..16 {
+            let input = vec![(0..256)
+                .map(|n| (2.0 * PI * 20000.0 * (chunk * 256 + n) as f64 / 48000.0).sin())
+                .collect::<Vec<f64>>()];
+            output.extend(resampler.process(&input, None).unwrap().remove(0));
+        }
+        let settled = &output[2 * resampler.output_delay()..];
+        (settled.iter().map(|v| v * v).sum::<f64>() / settled.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn custom_filter_cutoff() {
+        // 20 kHz is at 0.83 of the input Nyquist frequency.
+        assert!((resampled_rms(0.95) - 0.5f64.sqrt()).abs() < 0.01);
+        assert!(resampled_rms(0.5) < 1.0e-3);
+    }
+
+    #[test]
+    fn custom_filter_cutoff_invalid() {
+        for f_cutoff in [0.0, -0.5, 1.01, f32::NAN, f32::INFINITY] {
+            let parameters = FftFilterParameters {
+                f_cutoff: Some(f_cutoff),
+                ..Default::default()
+            };
+            assert!(matches!(
+                FftFixedIn::<f64>::new_with_filter(44100, 48000, 1024, 2, parameters, 2),
+                Err(ResamplerConstructionError::InvalidCutoff(_))
+            ));
+        }
+        let parameters = FftFilterParameters {
+            f_cutoff: Some(1.0),
+            ..Default::default()
+        };
+        assert!(FftFixedInOut::<f64>::new_with_filter(44100, 48000, 1024, parameters, 2).is_ok());
+    }
+
+    #[test]
+    fn check_fi_output_long_filter() {
+        let mut resampler =
+            FftFixedIn::<f64>::new_with_filter_length(44100, 48000, 4096, 8, 4096, 2).unwrap();
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn check_fo_output_resize() {
+        let mut resampler = FftFixedOut::<f64>::new(44100, 48000, 4096, 4, 2).unwrap();
+        assert_eq!(resampler.output_frames_next(), 4096);
+        // Use a whole number of FFT output units, to get the same number of input frames every time.
+        resampler.set_chunk_size(3360).unwrap();
+        assert_eq!(resampler.output_frames_next(), 3360);
+        assert_eq!(resampler.input_frames_next(), 3087);
+        assert_eq!(resampler.output_frames_max(), 4096);
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn check_fi_output_resize() {
+        let mut resampler = FftFixedIn::<f64>::new(44100, 48000, 4096, 4, 2).unwrap();
+        assert_eq!(resampler.input_frames_next(), 4096);
+        resampler.set_chunk_size(2048).unwrap();
+        assert_eq!(resampler.input_frames_next(), 2048);
+        assert_eq!(resampler.input_frames_max(), 4096);
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn clone_shares_filter() {
+        let resampler = FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap();
+        let cloned = resampler.clone();
+        assert!(Arc::ptr_eq(
+            &resampler.resampler.filter_f,
+            &cloned.resampler.filter_f
+        ));
+    }
+
+    #[test]
+    fn set_invalid_chunk_size() {
+        let mut resampler = FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap();
+        assert!(matches!(
+            resampler.set_chunk_size(2048),
+            Err(ResampleError::InvalidChunkSize {
+                max: 1024,
+                requested: 2048
+            })
+        ));
+        assert!(matches!(
+            resampler.set_chunk_size(0),
+            Err(ResampleError::InvalidChunkSize { .. })
+        ));
+        let mut resampler = FftFixedInOut::<f64>::new(44100, 48000, 1024, 2).unwrap();
+        assert!(matches!(
+            resampler.set_chunk_size(512),
+            Err(ResampleError::ChunkSizeNotAdjustable)
+        ));
+    }
+
+    #[test]
+    fn reset_chunk_size() {
+        let mut resampler = FftFixedOut::<f64>::new(44100, 48000, 1024, 2, 2).unwrap();
+        let frames = resampler.input_frames_next();
+        resampler.set_chunk_size(100).unwrap();
+        resampler.reset();
+        assert_eq!(resampler.output_frames_next(), 1024);
+        assert_eq!(resampler.input_frames_next(), frames);
+    }
+
+    #[test]
+    fn ratio_ntsc_pulldown() {
+        let resampler = FftFixedInOut::<f64>::new_with_ratio(
+            1000,
+            1001,
+            1024,
+            FftFilterParameters::default(),
+            2,
+        )
+        .unwrap();
+        assert_eq!(resampler.input_frames_next(), 2002);
+        assert_eq!(resampler.output_frames_next(), 2000);
+    }
+
+    #[test]
+    fn ratio_fractional_rate() {
+        // 48000 Hz to 48000.5 Hz
+        let mut resampler = FftFixedInOut::<f64>::new_with_ratio(
+            96001,
+            96000,
+            1024,
+            FftFilterParameters::default(),
+            2,
+        )
+        .unwrap();
+        assert_eq!(resampler.input_frames_next(), 96000);
+        assert_eq!(resampler.output_frames_next(), 96001);
+        check_output!(resampler);
+    }
+
+    #[test]
+    fn ratio_matches_rates() {
+        let mut from_rates = FftFixedIn::<f64>::new(44100, 48000, 1024, 2, 2).unwrap();
+        let mut from_ratio =
+            FftFixedIn::<f64>::new_with_ratio(480, 441, 1024, 2, FftFilterParameters::default(), 2)
+                .unwrap();
+        let mut rng = rand::thread_rng();
+        for _ in 0..3 {
+            let chunk: Vec<f64> = (0..1024).map(|_| rng.gen::<f64>() - 0.5).collect();
+            let waves = vec![chunk.clone(), chunk];
+            let out_rates = from_rates.process(&waves, None).unwrap();
+            let out_ratio = from_ratio.process(&waves, None).unwrap();
+            assert_eq!(out_rates, out_ratio);
+        }
+    }
+
+    #[test]
+    fn ratio_invalid() {
+        assert!(matches!(
+            FftFixedInOut::<f64>::new_with_ratio(0, 1001, 1024, FftFilterParameters::default(), 2),
+            Err(ResamplerConstructionError::InvalidRationalRatio {
+                numerator: 0,
+                denominator: 1001
+            })
+        ));
+        assert!(matches!(
+            FftFixedIn::<f64>::new_with_ratio(1000, 0, 1024, 2, FftFilterParameters::default(), 2),
+            Err(ResamplerConstructionError::InvalidRationalRatio {
+                numerator: 1000,
+                denominator: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn ratio_fft_too_long() {
+        // Coprime terms that would need an FFT of almost 10 million frames.
+        assert!(matches!(
+            FftFixedOut::<f64>::new_with_ratio(
+                9_999_991,
+                9_999_990,
+                1024,
+                2,
+                FftFilterParameters::default(),
+                2
+            ),
+            Err(ResamplerConstructionError::FftTooLong {
+                required: 9_999_991,
+                max: 1048576
+            })
+        ));
+        assert!(matches!(
+            FftFixedIn::<f64>::new_with_ratio(
+                u64::MAX,
+                3,
+                1024,
+                2,
+                FftFilterParameters::default(),
+                2
+            ),
+            Err(ResamplerConstructionError::FftTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn filter_too_long() {
+        // The FFT sizes are 1029 and 1120 frames, and the filter needs 1020 units.
+        // The partitions are designed at 160 times the input rate, 1020 * 1029 * 160 frames.
+        let parameters = FftFilterParameters {
+            filter_len: 1 << 20,
+            ..FftFilterParameters::default()
+        };
+        assert!(matches!(
+            FftFixedInOut::<f64>::new_with_ratio(160, 147, 1024, parameters, 2),
+            Err(ResamplerConstructionError::FftTooLong {
+                required: 167932800,
+                max: 1048576
+            })
+        ));
+        let parameters = FftFilterParameters {
+            filter_len: usize::MAX,
+            ..FftFilterParameters::default()
+        };
+        assert!(matches!(
+            FftFixedIn::<f64>::new_with_ratio(160, 147, 1024, 2, parameters, 2),
+            Err(ResamplerConstructionError::FftTooLong { .. })
+        ));
+        assert!(matches!(
+            FftFixedOut::<f64>::new_with_ratio(1, 1, 1, 1, parameters, 2),
+            Err(ResamplerConstructionError::FftTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn restore_state_resized() {
+        let mut resampler = FftFixedIn::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        let mut restored = FftFixedIn::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        resampler.set_chunk_size(300).unwrap();
+        check_state!(resampler, restored);
+        let mut resampler = FftFixedOut::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        let mut restored = FftFixedOut::<f64>::new(44100, 48000, 1000, 2, 2).unwrap();
+        resampler.set_chunk_size(300).unwrap();
+        check_state!(resampler, restored);
+        let mut smaller = FftFixedOut::<f64>::new(44100, 48000, 200, 2, 2).unwrap();
+        assert!(matches!(
+            smaller.set_state(&resampler.state()),
+            Err(ResampleError::IncompatibleState)
+        ));
+    }
+
     #[test]
     fn check_fi_max_output_length() {
         // parameters:
@@ -15,6 +15,13 @@ pub enum WindowFunction {
     Hann,
     /// Squared Hann. Slower rolloff and higher attenuation than simple Hann.
     Hann2,
+    /// Kaiser window with the given beta parameter.
+    /// A larger beta gives slower rolloff but higher attenuation.
+    /// Use [calculate_kaiser_beta] to get a beta value for a desired stopband attenuation.
+    Kaiser {
+        /// The beta parameter, controlling the shape of the window.
+        beta: f64,
+    },
 }
 
 /// Helper function. Standard Blackman-Harris window.
@@ -80,6 +87,40 @@ where
     window
 }
 
+/// Helper function. Modified Bessel function of the first kind, order zero.
+fn bessel_i0(value: f64) -> f64 {
+    let half = value / 2.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut k = 1.0;
+    while term > 1.0e-12 * sum {
+        term *= (half / k) * (half / k);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// Helper function. Kaiser window.
+// The window created is periodic.
+pub fn kaiser<T>(npoints: usize, beta: f64) -> Vec<T>
+where
+    T: Sample,
+{
+    trace!(
+        "Making a Kaiser window with {} points and beta {}",
+        npoints,
+        beta
+    );
+    let mut window = vec![T::zero(); npoints];
+    let norm = bessel_i0(beta);
+    for (x, item) in window.iter_mut().enumerate() {
+        let pos = 2.0 * x as f64 / npoints as f64 - 1.0;
+        *item = T::coerce(bessel_i0(beta * (1.0 - pos * pos).sqrt()) / norm);
+    }
+    window
+}
+
 /// Make the selected window function.
 pub fn make_window<T>(npoints: usize, windowfunc: WindowFunction) -> Vec<T>
 where
@@ -91,6 +132,7 @@ where
         }
         WindowFunction::Blackman | WindowFunction::Blackman2 => blackman::<T>(npoints),
         WindowFunction::Hann | WindowFunction::Hann2 => hann::<T>(npoints),
+        WindowFunction::Kaiser { beta } => kaiser::<T>(npoints, beta),
     };
     match windowfunc {
         WindowFunction::Blackman2 | WindowFunction::BlackmanHarris2 | WindowFunction::Hann2 => {
@@ -103,6 +145,9 @@ where
 
 /// Calculate a suitable relative cutoff frequency for the given sinc length using the given window function.
 /// The result is based on an approximation, which gives good results for sinc lengths from 32 to 2048.
+///
+/// For the [Kaiser](WindowFunction::Kaiser) window, the cutoff is instead calculated
+/// from the width of the main lobe of the window, which is valid for any beta.
 pub fn calculate_cutoff<T>(npoints: usize, windowfunc: WindowFunction) -> T
 where
     T: Sample,
@@ -139,6 +184,13 @@ where
             T::coerce(29.69451915489501),
             T::coerce(184.82117462266237),
         ),
+        // The main lobe of the Kaiser window extends sqrt(1 + (beta/pi)^2) bins from the center.
+        // Place the cutoff so that the stopband starts at the Nyquist frequency.
+        WindowFunction::Kaiser { beta } => (
+            T::coerce(2.0 * (1.0 + (beta / std::f64::consts::PI).powi(2)).sqrt()),
+            T::zero(),
+            T::zero(),
+        ),
     };
     let one = T::one();
     one / (k1 / T::coerce(npoints)
@@ -147,13 +199,28 @@ where
         + one)
 }
 
+/// Calculate the beta parameter of a [Kaiser](WindowFunction::Kaiser) window
+/// giving the desired stopband attenuation in dB.
+/// This uses the empirical formula by Kaiser.
+pub fn calculate_kaiser_beta(attenuation: f64) -> f64 {
+    if attenuation > 50.0 {
+        0.1102 * (attenuation - 8.7)
+    } else if attenuation >= 21.0 {
+        0.5842 * (attenuation - 21.0).powf(0.4) + 0.07886 * (attenuation - 21.0)
+    } else {
+        0.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     extern crate approx;
     use crate::windows::blackman;
     use crate::windows::blackman_harris;
     use crate::windows::calculate_cutoff;
+    use crate::windows::calculate_kaiser_beta;
     use crate::windows::hann;
+    use crate::windows::kaiser;
     use crate::windows::make_window;
     use crate::windows::WindowFunction;
     use approx::assert_abs_diff_eq;
@@ -222,4 +289,35 @@ mod tests {
         let cutoff = calculate_cutoff::<f64>(256, WindowFunction::Hann2);
         assert_abs_diff_eq!(cutoff, 0.979, epsilon = 0.001);
     }
+
+    #[test]
+    fn test_kaiser() {
+        let wnd = kaiser::<f64>(16, 8.0);
+        assert_abs_diff_eq!(wnd[8], 1.0, epsilon = 0.000001);
+        assert_abs_diff_eq!(wnd[0], 1.0 / 427.564, epsilon = 0.00001);
+        assert_abs_diff_eq!(wnd[4], wnd[12], epsilon = 0.000001);
+        let rect = make_window::<f64>(16, WindowFunction::Kaiser { beta: 0.0 });
+        assert!(rect.iter().all(|v| (v - 1.0).abs() < 0.000001));
+    }
+
+    #[test]
+    fn test_kaiser_beta() {
+        assert_abs_diff_eq!(calculate_kaiser_beta(100.0), 10.06126, epsilon = 0.00001);
+        assert_abs_diff_eq!(calculate_kaiser_beta(40.0), 3.39532, epsilon = 0.00001);
+        assert_abs_diff_eq!(calculate_kaiser_beta(10.0), 0.0, epsilon = 0.00001);
+    }
+
+    #[test]
+    fn test_kaiser_cutoff() {
+        // A Kaiser window with beta = pi*sqrt(8) has a main lobe of the same width
+        // as a Blackman window, compare with the fitted cutoff for Blackman.
+        let beta = std::f64::consts::PI * 8.0f64.sqrt();
+        for npoints in [128, 256, 1024] {
+            let kaiser_cutoff = calculate_cutoff::<f64>(npoints, WindowFunction::Kaiser { beta });
+            let blackman_cutoff = calculate_cutoff::<f64>(npoints, WindowFunction::Blackman);
+            assert_abs_diff_eq!(kaiser_cutoff, blackman_cutoff, epsilon = 0.003);
+        }
+        let cutoff = calculate_cutoff::<f64>(256, WindowFunction::Kaiser { beta: 10.0 });
+        assert_abs_diff_eq!(cutoff, 0.974, epsilon = 0.001);
+    }
 }